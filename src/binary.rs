@@ -0,0 +1,211 @@
+use crate::{parse_reply, vector_parse_error, AsVector, DataType};
+use redis::{FromRedisValue, RedisError, RedisResult, RedisWrite, ToRedisArgs, Value};
+use std::fmt;
+use std::str;
+
+/// A vector for `data_type BINARY` indices, stored as a bitset.
+///
+/// Bits are packed most significant bit first, so byte `0b1000_0000` is the
+/// vector `[1,0,0,0,0,0,0,0]`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BinaryVector {
+    bits: Vec<u8>,
+    dim: usize,
+}
+
+impl BinaryVector {
+    pub fn from_bools(values: &[bool]) -> Self {
+        let mut bits = vec![0u8; values.len().div_ceil(8)];
+        for (i, _) in values.iter().enumerate().filter(|(_, &b)| b) {
+            bits[i / 8] |= 0x80 >> (i % 8);
+        }
+        BinaryVector {
+            bits,
+            dim: values.len(),
+        }
+    }
+
+    /// Takes the first `dim` bits of `bytes`.
+    pub fn from_packed(bytes: &[u8], dim: usize) -> RedisResult<Self> {
+        if dim > bytes.len() * 8 {
+            return Err(vector_parse_error(format!(
+                "{} bytes cannot hold {} bits",
+                bytes.len(),
+                dim
+            )));
+        }
+        let mut bits = bytes[..dim.div_ceil(8)].to_vec();
+        if dim % 8 != 0 {
+            *bits.last_mut().unwrap() &= !(0xff >> (dim % 8));
+        }
+        Ok(BinaryVector { bits, dim })
+    }
+
+    pub fn len(&self) -> usize {
+        self.dim
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dim == 0
+    }
+
+    pub fn get(&self, i: usize) -> Option<bool> {
+        (i < self.dim).then(|| self.bits[i / 8] & (0x80 >> (i % 8)) != 0)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+        (0..self.dim).map(|i| self.bits[i / 8] & (0x80 >> (i % 8)) != 0)
+    }
+
+    /// The packed bits, with the unused bits of the last byte set to zero.
+    pub fn as_packed(&self) -> &[u8] {
+        &self.bits
+    }
+}
+
+impl From<&[bool]> for BinaryVector {
+    fn from(values: &[bool]) -> Self {
+        BinaryVector::from_bools(values)
+    }
+}
+
+impl fmt::Display for BinaryVector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[")?;
+        for (i, bit) in self.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            f.write_str(if bit { "1" } else { "0" })?;
+        }
+        f.write_str("]")
+    }
+}
+
+impl ToRedisArgs for BinaryVector {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        out.write_arg_fmt(self);
+    }
+}
+
+impl AsVector for BinaryVector {
+    fn fmt_vector(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+
+    fn check(&self) -> RedisResult<()> {
+        Ok(())
+    }
+
+    fn dim(&self) -> usize {
+        self.dim
+    }
+
+    fn data_type(&self) -> DataType {
+        DataType::Binary
+    }
+}
+
+impl str::FromStr for BinaryVector {
+    type Err = RedisError;
+
+    /// Parses the `[1,0,...]` format used by TairVector.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Accepts "1.0" style elements as well, since the server may report
+        // binary vectors with float formatting.
+        let values = s.parse::<crate::Vector>()?;
+        let bools = values
+            .0
+            .iter()
+            .enumerate()
+            .map(|(i, &x)| match x {
+                0.0 => Ok(false),
+                1.0 => Ok(true),
+                x => Err(vector_parse_error(format!(
+                    "element {} is not 0 or 1: {}",
+                    i, x
+                ))),
+            })
+            .collect::<RedisResult<Vec<bool>>>()?;
+        Ok(BinaryVector::from_bools(&bools))
+    }
+}
+
+impl FromRedisValue for BinaryVector {
+    fn from_redis_value(value: &Value) -> RedisResult<Self> {
+        parse_reply(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DataType, DistanceType, IndexSpec, IndexType, TairVectorCommands};
+    use redis::Iter;
+
+    #[test]
+    fn pack_and_encode() {
+        let v =
+            BinaryVector::from_bools(&[true, false, true, true, false, false, false, false, true]);
+        assert_eq!(v.len(), 9);
+        assert_eq!(v.as_packed(), [0b1011_0000, 0b1000_0000]);
+        assert_eq!(v.get(2), Some(true));
+        assert_eq!(v.get(9), None);
+        assert_eq!(v.to_string(), "[1,0,1,1,0,0,0,0,1]");
+        assert_eq!(v.to_redis_args(), vec![b"[1,0,1,1,0,0,0,0,1]".to_vec()]);
+
+        let packed = BinaryVector::from_packed(&[0b1011_0000, 0b1111_1111], 9).unwrap();
+        assert_eq!(packed, v);
+        assert!(BinaryVector::from_packed(&[0xff], 9).is_err());
+        assert!(BinaryVector::from_bools(&[]).is_empty());
+
+        let decoded: BinaryVector =
+            redis::from_redis_value(&Value::Data(b"[1,0,1,1,0,0,0,0,1]".to_vec())).unwrap();
+        assert_eq!(decoded, v);
+        let decoded: BinaryVector = "[1.0,0.0]".parse().unwrap();
+        assert_eq!(decoded, BinaryVector::from_bools(&[true, false]));
+        assert!("[1,2]".parse::<BinaryVector>().is_err());
+        assert!("1,0".parse::<BinaryVector>().is_err());
+    }
+
+    #[test]
+    fn binary_index() {
+        let redis_url = crate::mock::test_url();
+
+        let index_name = "test-binary-index";
+        let client = redis::Client::open(redis_url).unwrap();
+        let mut conn = client.get_connection().unwrap();
+
+        // cleanup
+        conn.tvs_del_index::<_, usize>(index_name).unwrap();
+
+        let spec = IndexSpec::new(index_name, 8, IndexType::Flat, DistanceType::Jaccard)
+            .data_type(DataType::Binary);
+        let created: bool = conn.tvs_create_index_spec(&spec).unwrap();
+        assert!(created);
+
+        let v1 = BinaryVector::from_packed(&[0b1111_0000], 8).unwrap();
+        let v2 = BinaryVector::from_packed(&[0b0000_1111], 8).unwrap();
+        let _: usize = conn.tvs_hset_vector(index_name, "k1", &v1).unwrap();
+        let _: usize = conn.tvs_hset_vector(index_name, "k2", &v2).unwrap();
+
+        let got: Vec<BinaryVector> = conn.tvs_get_vector(index_name, "k1").unwrap();
+        assert_eq!(got[0], v1);
+
+        let query = BinaryVector::from_packed(&[0b1110_0000], 8).unwrap();
+        let knn_results: Vec<(String, f32)> = conn.tvs_knnsearch(index_name, 2, &query).unwrap();
+        assert_eq!(knn_results.len(), 2);
+        assert_eq!(knn_results[0].0, "k1");
+        assert!((knn_results[0].1 - 0.25).abs() < 1e-6);
+        assert!((knn_results[1].1 - 1.0).abs() < 1e-6);
+
+        let iter: Iter<String> = conn.tvs_scan_max_dist(index_name, &query, 0.5).unwrap();
+        let keys: Vec<String> = iter.collect();
+        assert_eq!(keys, ["k1"]);
+
+        conn.tvs_del_index::<_, usize>(index_name).unwrap();
+    }
+}
@@ -1,64 +1,443 @@
-use ndarray::parallel::prelude::*;
 use ndarray::prelude::*;
-use std::sync::Mutex;
+use rayon::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, PoisonError};
 
-use crate::NdArrayVector;
-use crate::TairVectorCommands;
+use crate::{NdArrayVector, TairVectorError, TairVectorPipeline, VectorArg, VectorElement};
 
 pub trait BulkOps {
-    fn bulk_load(&self, index_name: &str, data: &Array2<f32>) -> usize;
+    /// `bulk_load_with_options` with the default options.
+    fn bulk_load<T: VectorElement + Sync>(
+        &self,
+        index_name: &str,
+        data: &Array2<T>,
+    ) -> Result<BulkLoadReport, TairVectorError>;
+
+    /// Writes each row of `data` with TVS.HSET, keyed by its row number.
+    ///
+    /// Fails only if the thread pool or its connections cannot be set up.
+    /// Rows that cannot be written are listed in the report.
+    fn bulk_load_with_options<T: VectorElement + Sync>(
+        &self,
+        index_name: &str,
+        data: &Array2<T>,
+        options: &BulkLoadOptions,
+    ) -> Result<BulkLoadReport, TairVectorError>;
+
+    /// Writes each entry with TVS.HSET, the vector along with its attributes.
+    ///
+    /// Fails if a key slice or a column does not have a value for every row.
+    fn bulk_load_entries<T: VectorElement + Sync>(
+        &self,
+        index_name: &str,
+        entries: &BulkEntries<'_, T>,
+        options: &BulkLoadOptions,
+    ) -> Result<BulkLoadReport, TairVectorError>;
+}
+
+type RowFn<'a, R> = Box<dyn Fn(usize) -> R + Sync + 'a>;
+
+/// The rows of an array with their keys and attributes, for
+/// `BulkOps::bulk_load_entries`.
+///
+/// Keys default to the row number. Attributes of a row come from the
+/// columns in order, then from the `attributes` closure.
+///
+/// ```
+/// use ndarray::Array2;
+/// use tair_vector_rs::BulkEntries;
+///
+/// let data = Array2::<f32>::zeros((2, 4));
+/// let tags = ["red", "blue"];
+/// let entries = BulkEntries::new(&data)
+///     .keys(&["sku-1", "sku-2"])
+///     .column("price", &[9.5, 12.0])
+///     .attributes(|row| [("tag", tags[row])]);
+/// ```
+pub struct BulkEntries<'a, T> {
+    data: ArrayView2<'a, T>,
+    keys: RowFn<'a, String>,
+    columns: Vec<(String, RowFn<'a, String>)>,
+    attributes: Option<RowFn<'a, Vec<(String, String)>>>,
+    /// Row count of each slice, checked against the data before loading.
+    lengths: Vec<(String, usize)>,
+}
+
+impl<'a, T> BulkEntries<'a, T> {
+    pub fn new(data: &'a Array2<T>) -> Self {
+        BulkEntries {
+            data: data.view(),
+            keys: Box::new(|row| row.to_string()),
+            columns: vec![],
+            attributes: None,
+            lengths: vec![],
+        }
+    }
+
+    /// The key of each row.
+    pub fn keys<K: ToString + Sync>(mut self, keys: &'a [K]) -> Self {
+        self.keys = Box::new(|row| keys[row].to_string());
+        self.lengths.retain(|(name, _)| name != "keys");
+        self.lengths.push(("keys".to_owned(), keys.len()));
+        self
+    }
+
+    /// Computes the key of a row from its number.
+    pub fn key_fn<K, F>(mut self, f: F) -> Self
+    where
+        K: ToString,
+        F: Fn(usize) -> K + Sync + 'a,
+    {
+        self.keys = Box::new(move |row| f(row).to_string());
+        self.lengths.retain(|(name, _)| name != "keys");
+        self
+    }
+
+    /// An attribute with a value for each row.
+    pub fn column<V: ToString + Sync>(mut self, field: &str, values: &'a [V]) -> Self {
+        self.columns
+            .push((field.to_owned(), Box::new(|row| values[row].to_string())));
+        self.lengths
+            .push((format!("column {}", field), values.len()));
+        self
+    }
+
+    /// Computes the attributes of a row from its number.
+    pub fn attributes<I, F, V, G>(mut self, f: G) -> Self
+    where
+        I: IntoIterator<Item = (F, V)>,
+        F: ToString,
+        V: ToString,
+        G: Fn(usize) -> I + Sync + 'a,
+    {
+        self.attributes = Some(Box::new(move |row| {
+            f(row)
+                .into_iter()
+                .map(|(field, value)| (field.to_string(), value.to_string()))
+                .collect()
+        }));
+        self
+    }
+
+    fn check(&self) -> Result<(), TairVectorError> {
+        let nrows = self.data.nrows();
+        match self.lengths.iter().find(|(_, len)| *len != nrows) {
+            Some((name, len)) => Err(TairVectorError::InvalidParameter(format!(
+                "{} has {} rows, expected {}",
+                name, len, nrows
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// The VECTOR field of `row` followed by its attributes.
+    fn fields(&self, row: usize) -> redis::RedisResult<Vec<(String, String)>>
+    where
+        T: VectorElement,
+    {
+        let vector = NdArrayVector::new(self.data.row(row))?;
+        let mut fields = vec![("VECTOR".to_owned(), VectorArg(vector).to_string())];
+        for (field, value) in &self.columns {
+            fields.push((field.clone(), value(row)));
+        }
+        if let Some(attributes) = &self.attributes {
+            fields.extend(attributes(row));
+        }
+        Ok(fields)
+    }
+}
+
+/// What to do when a row cannot be written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BulkErrorPolicy {
+    /// Stop loading at the first failed row. Rows already sent, in the same
+    /// pipeline or by other threads, are still written, the others are
+    /// reported as skipped.
+    #[default]
+    Stop,
+    /// Keep loading and report every failed row.
+    Collect,
+}
+
+/// Options of `BulkOps::bulk_load_with_options`.
+///
+/// Built with `new` and the setters, so that options can be added later.
+///
+/// ```
+/// use tair_vector_rs::{BulkErrorPolicy, BulkLoadOptions};
+///
+/// let options = BulkLoadOptions::new()
+///     .batch_size(256)
+///     .threads(4)
+///     .pipeline_depth(32)
+///     .on_error(BulkErrorPolicy::Collect);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct BulkLoadOptions {
+    /// Rows handed to a thread at a time.
+    pub batch_size: usize,
+    /// Size of the dedicated thread pool, one connection per thread. `None`
+    /// uses the rayon default, one thread per CPU.
+    pub threads: Option<usize>,
+    /// Rows sent per round trip.
+    pub pipeline_depth: usize,
+    /// Whether to stop at the first row that cannot be written or to
+    /// report them all.
+    pub on_error: BulkErrorPolicy,
+}
+
+impl Default for BulkLoadOptions {
+    fn default() -> Self {
+        BulkLoadOptions {
+            batch_size: 32,
+            threads: None,
+            pipeline_depth: 8,
+            on_error: BulkErrorPolicy::default(),
+        }
+    }
+}
+
+impl BulkLoadOptions {
+    pub fn new() -> Self {
+        BulkLoadOptions::default()
+    }
+
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
+    pub fn pipeline_depth(mut self, pipeline_depth: usize) -> Self {
+        self.pipeline_depth = pipeline_depth;
+        self
+    }
+
+    pub fn on_error(mut self, on_error: BulkErrorPolicy) -> Self {
+        self.on_error = on_error;
+        self
+    }
+
+    fn check(&self) -> Result<(), TairVectorError> {
+        for (name, value) in [
+            ("batch_size", Some(self.batch_size)),
+            ("threads", self.threads),
+            ("pipeline_depth", Some(self.pipeline_depth)),
+        ] {
+            if value == Some(0) {
+                return Err(TairVectorError::InvalidParameter(format!(
+                    "{} must be positive",
+                    name
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of a bulk load.
+#[derive(Debug, Default)]
+pub struct BulkLoadReport {
+    /// Number of rows written.
+    pub loaded: usize,
+    /// Rows that could not be written, by row number in ascending order.
+    pub failed: Vec<(usize, TairVectorError)>,
+    /// Number of rows not attempted after loading stopped.
+    pub skipped: usize,
+}
+
+impl BulkLoadReport {
+    /// Whether every row was written.
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty() && self.skipped == 0
+    }
+
+    fn merge(mut self, other: BulkLoadReport) -> Self {
+        self.loaded += other.loaded;
+        self.failed.extend(other.failed);
+        self.skipped += other.skipped;
+        self
+    }
 }
 
 impl BulkOps for redis::Client {
-    fn bulk_load(&self, index_name: &str, data: &Array2<f32>) -> usize {
-        // set rayon gloabl thread pool
-        // rayon::ThreadPoolBuilder::new()
-        //     .num_threads(4)
-        //     .build_global()
-        //     .unwrap();
-
-        // split data into batches
-        let batch_size: usize = 32;
-        let batches: Vec<ArrayView2<f32>> = data.axis_chunks_iter(Axis(0), batch_size).collect();
-
-        // create a connection for each thread
-        let thread_conns: Vec<Mutex<redis::Connection>> = (0..rayon::current_num_threads())
-            .map(|_| Mutex::new(self.get_connection().unwrap()))
-            .collect();
+    fn bulk_load<T: VectorElement + Sync>(
+        &self,
+        index_name: &str,
+        data: &Array2<T>,
+    ) -> Result<BulkLoadReport, TairVectorError> {
+        self.bulk_load_with_options(index_name, data, &BulkLoadOptions::default())
+    }
+
+    fn bulk_load_with_options<T: VectorElement + Sync>(
+        &self,
+        index_name: &str,
+        data: &Array2<T>,
+        options: &BulkLoadOptions,
+    ) -> Result<BulkLoadReport, TairVectorError> {
+        load_rows(self, data.nrows(), options, |pipe, row| {
+            let vector = NdArrayVector::new(data.row(row))?;
+            pipe.tvs_hset_vector(index_name, row, vector);
+            Ok(())
+        })
+    }
 
+    fn bulk_load_entries<T: VectorElement + Sync>(
+        &self,
+        index_name: &str,
+        entries: &BulkEntries<'_, T>,
+        options: &BulkLoadOptions,
+    ) -> Result<BulkLoadReport, TairVectorError> {
+        entries.check()?;
+        load_rows(self, entries.data.nrows(), options, |pipe, row| {
+            let fields = entries.fields(row)?;
+            pipe.tvs_hset_multi(index_name, (entries.keys)(row), &fields);
+            Ok(())
+        })
+    }
+}
+
+/// Loads rows `0..nrows` in parallel, `add_row` queueing the commands of a
+/// row on a pipeline.
+fn load_rows<F>(
+    client: &redis::Client,
+    nrows: usize,
+    options: &BulkLoadOptions,
+    add_row: F,
+) -> Result<BulkLoadReport, TairVectorError>
+where
+    F: Fn(&mut redis::Pipeline, usize) -> redis::RedisResult<()> + Sync,
+{
+    options.check()?;
+    if nrows == 0 {
+        return Ok(BulkLoadReport::default());
+    }
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(options.threads.unwrap_or(0))
+        .build()
+        .map_err(|e| TairVectorError::InvalidParameter(e.to_string()))?;
+
+    // a connection for each thread of the pool
+    let conns = (0..pool.current_num_threads())
+        .map(|_| client.get_connection().map(Mutex::new))
+        .collect::<redis::RedisResult<Vec<_>>>()?;
+    let stop = AtomicBool::new(false);
+
+    let batches: Vec<(usize, usize)> = (0..nrows)
+        .step_by(options.batch_size)
+        .map(|start| (start, nrows.min(start + options.batch_size)))
+        .collect();
+    let mut report = pool.install(|| {
         batches
             .par_iter()
-            .enumerate()
-            .map(|(i, batch)| -> usize {
-                let mut conn = thread_conns[rayon::current_thread_index().unwrap()]
-                    .lock()
-                    .unwrap();
-                batch
-                    .outer_iter()
-                    .enumerate()
-                    .map(|(j, v)| -> usize {
-                        conn.tvs_hset_vector(
-                            index_name,
-                            i * batch_size + j,
-                            &NdArrayVector { 0: v },
-                        )
-                        .unwrap()
-                    })
-                    .sum::<usize>()
+            .map(|&(start, end)| {
+                let thread = rayon::current_thread_index().unwrap_or_default();
+                let mut conn = conns[thread].lock().unwrap_or_else(PoisonError::into_inner);
+                load_batch(&mut conn, start..end, options, &stop, &add_row)
             })
-            .sum::<usize>()
+            .reduce(BulkLoadReport::default, BulkLoadReport::merge)
+    });
+    report.failed.sort_by_key(|(row, _)| *row);
+    Ok(report)
+}
+
+fn load_batch<F>(
+    conn: &mut redis::Connection,
+    rows: std::ops::Range<usize>,
+    options: &BulkLoadOptions,
+    stop: &AtomicBool,
+    add_row: &F,
+) -> BulkLoadReport
+where
+    F: Fn(&mut redis::Pipeline, usize) -> redis::RedisResult<()>,
+{
+    let mut report = BulkLoadReport::default();
+    let mut next = rows.start;
+    while next < rows.end && !stop.load(Ordering::Relaxed) {
+        let mut pipe = redis::pipe();
+        let mut queued = vec![];
+        while next < rows.end && queued.len() < options.pipeline_depth {
+            let row = next;
+            next += 1;
+            if let Err(e) = add_row(&mut pipe, row) {
+                // rejected before sending, e.g. a non-finite element
+                report
+                    .failed
+                    .push((row, TairVectorError::InvalidParameter(e.to_string())));
+                if options.on_error == BulkErrorPolicy::Stop {
+                    stop.store(true, Ordering::Relaxed);
+                    break;
+                }
+                continue;
+            }
+            queued.push(row);
+        }
+        send_rows(conn, &pipe, &queued, &mut report);
+        if !report.failed.is_empty() && options.on_error == BulkErrorPolicy::Stop {
+            stop.store(true, Ordering::Relaxed);
+        }
+    }
+    report.skipped += rows.end - next;
+    report
+}
+
+/// Sends the pipeline of `rows`, one command per row, and reads the reply of
+/// each row on its own: `Pipeline::query` only returns the first error.
+///
+/// Nothing is sent twice. After an IO error, the rows without a reply are
+/// reported as failed, although the server may have written them.
+fn send_rows(
+    conn: &mut redis::Connection,
+    pipe: &redis::Pipeline,
+    rows: &[usize],
+    report: &mut BulkLoadReport,
+) {
+    if rows.is_empty() {
+        return;
+    }
+    if let Err(e) = conn.send_packed_command(&pipe.get_packed_pipeline()) {
+        for &row in rows {
+            report.failed.push((row, connection_lost(&e)));
+        }
+        return;
     }
+    for (i, &row) in rows.iter().enumerate() {
+        match conn.recv_response() {
+            Ok(_) => report.loaded += 1,
+            Err(e) if e.is_io_error() => {
+                for &row in &rows[i + 1..] {
+                    report.failed.push((row, connection_lost(&e)));
+                }
+                report.failed.push((row, e.into()));
+                return;
+            }
+            Err(e) => report.failed.push((row, e.into())),
+        }
+    }
+}
+
+fn connection_lost(e: &redis::RedisError) -> TairVectorError {
+    let detail = format!("no reply after: {}", e);
+    redis::RedisError::from((redis::ErrorKind::IoError, "Connection lost", detail)).into()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::Vector;
+    use crate::{field, IndexInfo, KnnQuery, KnnResult, TairVectorCommands, Vector};
     use ndarray_rand::rand_distr::Uniform;
     use ndarray_rand::RandomExt;
     use redis::Iter;
     use std::collections::HashMap;
-    use std::env;
+    use std::io::Read;
+    use std::net::TcpListener;
+    use std::sync::atomic::AtomicUsize;
+    use std::thread;
 
     #[test]
     fn bulk_load() {
@@ -70,11 +449,7 @@ mod tests {
         let uniform = Uniform::<f32>::new(0.0, 1.0);
         let vecs: Array2<f32> = Array::random_using((nvecs, dim), uniform, &mut rng);
 
-        let redis_url = if let Ok(v) = env::var("TAIR_URL") {
-            v
-        } else {
-            String::from("redis://127.0.0.1/")
-        };
+        let redis_url = crate::mock::test_url();
 
         let index_name = "test-bulk-load";
         let client = redis::Client::open(redis_url).unwrap();
@@ -88,16 +463,14 @@ mod tests {
             .unwrap();
         assert!(created);
 
-        let loaded = client.bulk_load(index_name, &vecs);
-        assert_eq!(loaded, vecs.nrows());
+        let report = client.bulk_load(index_name, &vecs).unwrap();
+        assert!(report.is_complete());
+        assert_eq!(report.loaded, vecs.nrows());
         let query: Array1<f32> = Array::random_using(dim, uniform, &mut rng);
 
-        let index_info: HashMap<String, String> = conn.tvs_get_index(index_name).unwrap();
-        assert!(index_info.len() > 0);
-        assert_eq!(
-            index_info.get("data_count").unwrap().to_owned(),
-            nvecs.to_string()
-        );
+        let index_info: IndexInfo = conn.tvs_get_index(index_name).unwrap();
+        assert_eq!(index_info.dimension, dim);
+        assert_eq!(index_info.data_count, nvecs as u64);
 
         let key_iter: Iter<String> = conn.tvs_scan(index_name).unwrap();
         let scanned_keys: Vec<String> = key_iter.collect();
@@ -106,17 +479,220 @@ mod tests {
             let idx: usize = key.parse().unwrap();
             let v: Vec<Vector> = conn.tvs_get_vector(index_name, key).unwrap();
             assert_eq!(v.len(), 1);
-            assert_eq!(v[0].0.len(), dim);
-            for i in 0..dim {
-                assert!((v[0].0[i] - vecs.row(idx)[i]).abs() < 1e-6);
+            assert_eq!(v[0].len(), dim);
+            for (x, y) in v[0].iter().zip(vecs.row(idx)) {
+                assert!((x - y).abs() < 1e-6);
             }
         }
 
         let knn_results: Vec<(String, f32)> = conn
-            .tvs_knnsearch(index_name, 10, &NdArrayVector { 0: query.view() })
+            .tvs_knnsearch(index_name, 10, NdArrayVector::new(query.view()).unwrap())
             .unwrap();
         assert_eq!(knn_results.len(), 10);
 
+        let mknn_results: Vec<Vec<(String, f32)>> = conn
+            .tvs_mknnsearch_ndarray(index_name, 10, vecs.slice(s![0..4, ..]))
+            .unwrap();
+        assert_eq!(mknn_results.len(), 4);
+        for (i, results) in mknn_results.iter().enumerate() {
+            assert_eq!(results.len(), 10);
+            assert_eq!(results[0].0, i.to_string());
+        }
+
+        let mut invalid = vecs.slice(s![0..2, ..]).to_owned();
+        invalid[[1, 0]] = f32::INFINITY;
+        let err = conn
+            .tvs_mknnsearch_ndarray::<_, _, Vec<Vec<(String, f32)>>>(index_name, 10, invalid.view())
+            .unwrap_err();
+        assert_eq!(err.kind(), redis::ErrorKind::ClientError);
+
+        conn.tvs_del_index::<_, usize>(index_name).unwrap();
+    }
+
+    #[test]
+    fn bulk_load_errors() {
+        let index_name = "test-bulk-load-errors";
+        let client = redis::Client::open(crate::mock::test_url()).unwrap();
+        let mut conn = client.get_connection().unwrap();
+
+        // cleanup
+        conn.tvs_del_index::<_, usize>(index_name).unwrap();
+
+        let created: bool = conn.tvs_create_index(index_name, 2, "FLAT", "L2").unwrap();
+        assert!(created);
+
+        let mut vecs = Array2::<f32>::zeros((10, 2));
+        vecs[[3, 0]] = f32::NAN;
+        let options = BulkLoadOptions::new()
+            .batch_size(4)
+            .threads(2)
+            .pipeline_depth(3)
+            .on_error(BulkErrorPolicy::Collect);
+        let report = client
+            .bulk_load_with_options(index_name, &vecs, &options)
+            .unwrap();
+        assert_eq!(report.loaded, 9);
+        assert_eq!(report.skipped, 0);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, 3);
+
+        // every row fails on the server, loading stops at the first one
+        let vecs = Array2::<f32>::zeros((10, 3));
+        let options = BulkLoadOptions::new().batch_size(4).threads(1);
+        let report = client
+            .bulk_load_with_options(index_name, &vecs, &options)
+            .unwrap();
+        assert_eq!(report.loaded, 0);
+        assert_eq!(report.failed.len(), 4);
+        assert_eq!(report.skipped, 6);
+        assert!(matches!(
+            report.failed[0],
+            (0, TairVectorError::DimensionMismatch { .. })
+        ));
+
+        // stops inside the pipeline, rows after the failed one are skipped
+        let mut vecs = Array2::<f32>::zeros((10, 2));
+        vecs[[3, 0]] = f32::NAN;
+        let options = BulkLoadOptions::new()
+            .batch_size(10)
+            .threads(1)
+            .pipeline_depth(10);
+        let report = client
+            .bulk_load_with_options(index_name, &vecs, &options)
+            .unwrap();
+        assert_eq!(report.loaded, 3);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.skipped, 6);
+
+        // a row rejected by the server in the middle of a pipeline, every
+        // row is sent once
+        let calls = AtomicUsize::new(0);
+        let data = Array2::<f32>::zeros((6, 2));
+        let entries = BulkEntries::new(&data).attributes(|row| {
+            calls.fetch_add(1, Ordering::Relaxed);
+            if row == 1 {
+                vec![("VECTOR", "[x]")]
+            } else {
+                vec![]
+            }
+        });
+        let options = BulkLoadOptions::new()
+            .threads(1)
+            .pipeline_depth(6)
+            .on_error(BulkErrorPolicy::Collect);
+        let report = client
+            .bulk_load_entries(index_name, &entries, &options)
+            .unwrap();
+        assert_eq!(report.loaded, 5);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, 1);
+        assert_eq!(calls.load(Ordering::Relaxed), 6);
+
+        let options = BulkLoadOptions::new().pipeline_depth(0);
+        assert!(matches!(
+            client.bulk_load_with_options(index_name, &vecs, &options),
+            Err(TairVectorError::InvalidParameter(_))
+        ));
+
+        // nothing to load, no connection is opened
+        let unreachable = redis::Client::open("redis://127.0.0.1:1/").unwrap();
+        let report = unreachable
+            .bulk_load(index_name, &Array2::<f32>::zeros((0, 2)))
+            .unwrap();
+        assert!(report.is_complete());
+        assert_eq!(report.loaded, 0);
+
+        conn.tvs_del_index::<_, usize>(index_name).unwrap();
+    }
+
+    #[test]
+    fn bulk_load_connection_lost() {
+        // a server that hangs up on the first pipeline
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("redis://{}/", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = stream.read(&mut [0; 1024]);
+        });
+
+        let calls = AtomicUsize::new(0);
+        let data = Array2::<f32>::zeros((4, 2));
+        let entries = BulkEntries::new(&data).attributes(|_| {
+            calls.fetch_add(1, Ordering::Relaxed);
+            [("tag", "a")]
+        });
+        let options = BulkLoadOptions::new()
+            .threads(1)
+            .pipeline_depth(2)
+            .on_error(BulkErrorPolicy::Collect);
+        let report = redis::Client::open(url)
+            .unwrap()
+            .bulk_load_entries("idx", &entries, &options)
+            .unwrap();
+        assert_eq!(report.loaded, 0);
+        assert_eq!(report.failed.len(), 4);
+        assert!(report.failed.iter().all(|(_, e)| matches!(
+            e,
+            TairVectorError::Transport(e) if e.kind() == redis::ErrorKind::IoError
+        )));
+        // nothing is retried row by row
+        assert_eq!(calls.load(Ordering::Relaxed), 4);
+    }
+
+    #[test]
+    fn bulk_load_entries() {
+        let index_name = "test-bulk-load-entries";
+        let client = redis::Client::open(crate::mock::test_url()).unwrap();
+        let mut conn = client.get_connection().unwrap();
+
+        // cleanup
+        conn.tvs_del_index::<_, usize>(index_name).unwrap();
+
+        let created: bool = conn.tvs_create_index(index_name, 2, "FLAT", "L2").unwrap();
+        assert!(created);
+
+        let data = Array2::from_shape_fn((3, 2), |(i, j)| (i * 2 + j) as f32);
+        let keys = ["sku-a", "sku-b", "sku-c"];
+        let prices = [9.5, 12.0, 30.0];
+        let tags = ["red", "blue", "red"];
+        let entries = BulkEntries::new(&data)
+            .keys(&keys)
+            .column("price", &prices)
+            .attributes(|row| [("tag", tags[row])]);
+        let options = BulkLoadOptions::new().threads(2).pipeline_depth(2);
+        let report = client
+            .bulk_load_entries(index_name, &entries, &options)
+            .unwrap();
+        assert!(report.is_complete());
+        assert_eq!(report.loaded, 3);
+
+        let got: HashMap<String, String> = conn.tvs_hgetall(index_name, "sku-b").unwrap();
+        assert_eq!(got["price"], "12");
+        assert_eq!(got["tag"], "blue");
+        let v: Vec<Vector> = conn.tvs_get_vector(index_name, "sku-b").unwrap();
+        assert_eq!(*v[0], [2.0, 3.0]);
+
+        let query = KnnQuery::new(3).filter(field("tag").eq("red"));
+        let result: KnnResult = conn
+            .tvs_knnsearch_query(index_name, [0.0f32, 0.0], &query)
+            .unwrap();
+        let found: Vec<_> = result.iter().map(|hit| hit.key.as_str()).collect();
+        assert_eq!(found, ["sku-a", "sku-c"]);
+
+        let entries = BulkEntries::new(&data).key_fn(|row| format!("item:{}", row));
+        let report = client
+            .bulk_load_entries(index_name, &entries, &options)
+            .unwrap();
+        assert_eq!(report.loaded, 3);
+        let keys: Vec<String> = conn.tvs_scan(index_name).unwrap().collect();
+        assert_eq!(keys.len(), 6);
+
+        let entries = BulkEntries::new(&data).column("price", &prices[..2]);
+        assert!(matches!(
+            client.bulk_load_entries(index_name, &entries, &options),
+            Err(TairVectorError::InvalidParameter(_))
+        ));
+
         conn.tvs_del_index::<_, usize>(index_name).unwrap();
     }
 }
@@ -0,0 +1,522 @@
+#[cfg(feature = "cluster-async")]
+use redis::cluster_routing::{Route, RoutingInfo, SingleNodeRoutingInfo, SlotAddr};
+#[cfg(feature = "cluster-async")]
+use redis::RedisFuture;
+use redis::{
+    ConnectionAddr, ConnectionInfo, ErrorKind, FromRedisValue, RedisError, RedisResult,
+    ToRedisArgs, Value,
+};
+use std::any::type_name;
+use std::collections::BTreeMap;
+
+/// Commands that need to visit every master of a cluster.
+///
+/// Commands naming a single index are routed by the slot of the index name
+/// and work on cluster connections through `TairVectorCommands` as is.
+/// `TVS.SCANINDEX` has no key, and the multi-index searches start with the
+/// number of indices, which the cluster would route as a key. On cluster
+/// connections `tvs_scan_index`, `tvs_mindex_knnsearch` and
+/// `tvs_mindex_mknnsearch` therefore fail without sending anything.
+///
+/// The sync cluster connection cannot send a command to a chosen node, so the
+/// scan opens its own connection to each master listed by CLUSTER SLOTS.
+/// `node` supplies everything but the address of those connections: the
+/// credentials, the database and the TLS mode, usually the `ConnectionInfo`
+/// the cluster client was built from.
+pub trait TairVectorClusterCommands {
+    /// TVS.SCANINDEX on every master, one after the other
+    fn tvs_cluster_scan_index<K: FromRedisValue>(
+        &mut self,
+        node: &ConnectionInfo,
+    ) -> RedisResult<ClusterScanIndex<K>>;
+
+    /// TVS.SCANINDEX cursor MATCH pattern on every master, one after the other
+    fn tvs_cluster_scan_index_match<P: ToRedisArgs, K: FromRedisValue>(
+        &mut self,
+        node: &ConnectionInfo,
+        pattern: P,
+    ) -> RedisResult<ClusterScanIndex<K>>;
+}
+
+/// Async version of `TairVectorClusterCommands`. The pages are requested
+/// through the cluster connection itself, routed to each master in turn.
+#[cfg(feature = "cluster-async")]
+pub trait TairVectorClusterAsyncCommands {
+    /// TVS.SCANINDEX on every master, one after the other
+    fn tvs_cluster_scan_index<K: FromRedisValue + Send>(
+        &mut self,
+    ) -> RedisFuture<'_, ClusterScanIndexAsync<'_, K>>;
+
+    /// TVS.SCANINDEX cursor MATCH pattern on every master, one after the other
+    fn tvs_cluster_scan_index_match<'a, P: ToRedisArgs + Send + 'a, K: FromRedisValue + Send>(
+        &'a mut self,
+        pattern: P,
+    ) -> RedisFuture<'a, ClusterScanIndexAsync<'a, K>>;
+}
+
+impl TairVectorClusterCommands for redis::cluster::ClusterConnection {
+    fn tvs_cluster_scan_index<K: FromRedisValue>(
+        &mut self,
+        node: &ConnectionInfo,
+    ) -> RedisResult<ClusterScanIndex<K>> {
+        let slots: Value = redis::cmd("CLUSTER").arg("SLOTS").query(self)?;
+        ClusterScanIndex::new(&slots, node, None)
+    }
+
+    fn tvs_cluster_scan_index_match<P: ToRedisArgs, K: FromRedisValue>(
+        &mut self,
+        node: &ConnectionInfo,
+        pattern: P,
+    ) -> RedisResult<ClusterScanIndex<K>> {
+        let slots: Value = redis::cmd("CLUSTER").arg("SLOTS").query(self)?;
+        ClusterScanIndex::new(&slots, node, Some(pattern.to_redis_args()))
+    }
+}
+
+/// Iterates over the indices of every master, following the cursor of each
+/// master until it returns 0 before moving on to the next one.
+///
+/// An error ends the iteration after it is yielded.
+pub struct ClusterScanIndex<K> {
+    nodes: std::vec::IntoIter<ConnectionInfo>,
+    // the current master, while its cursor is not 0
+    conn: Option<redis::Connection>,
+    cursor: u64,
+    pattern: Option<Vec<Vec<u8>>>,
+    batch: std::vec::IntoIter<K>,
+}
+
+impl<K: FromRedisValue> ClusterScanIndex<K> {
+    fn new(
+        slots: &Value,
+        node: &ConnectionInfo,
+        pattern: Option<Vec<Vec<u8>>>,
+    ) -> RedisResult<Self> {
+        let nodes: Vec<ConnectionInfo> = master_slots(slots)?
+            .into_keys()
+            .map(|(host, port)| node_info(node, host, port))
+            .collect();
+        Ok(ClusterScanIndex {
+            nodes: nodes.into_iter(),
+            conn: None,
+            cursor: 0,
+            pattern,
+            batch: vec![].into_iter(),
+        })
+    }
+
+    fn next_page(&mut self) -> RedisResult<()> {
+        let mut conn = match self.conn.take() {
+            Some(conn) => conn,
+            None => {
+                self.cursor = 0;
+                match self.nodes.next() {
+                    Some(info) => redis::Client::open(info)?.get_connection()?,
+                    None => return Ok(()),
+                }
+            }
+        };
+        let (next, items): (u64, Vec<K>) =
+            scan_index_cmd(self.cursor, self.pattern.as_ref()).query(&mut conn)?;
+        self.batch = items.into_iter();
+        self.cursor = next;
+        if next != 0 {
+            self.conn = Some(conn);
+        }
+        Ok(())
+    }
+}
+
+impl<K: FromRedisValue> Iterator for ClusterScanIndex<K> {
+    type Item = RedisResult<K>;
+
+    fn next(&mut self) -> Option<RedisResult<K>> {
+        loop {
+            if let Some(item) = self.batch.next() {
+                return Some(Ok(item));
+            }
+            if self.conn.is_none() && self.nodes.len() == 0 {
+                return None;
+            }
+            if let Err(e) = self.next_page() {
+                self.conn = None;
+                self.nodes = vec![].into_iter();
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+/// The connection to a master: the address from CLUSTER SLOTS, the rest from
+/// `node`.
+fn node_info(node: &ConnectionInfo, host: String, port: u16) -> ConnectionInfo {
+    let addr = match &node.addr {
+        ConnectionAddr::TcpTls {
+            insecure,
+            tls_params,
+            ..
+        } => ConnectionAddr::TcpTls {
+            host,
+            port,
+            insecure: *insecure,
+            tls_params: tls_params.clone(),
+        },
+        _ => ConnectionAddr::Tcp(host, port),
+    };
+    ConnectionInfo {
+        addr,
+        redis: node.redis.clone(),
+    }
+}
+
+#[cfg(feature = "cluster-async")]
+impl TairVectorClusterAsyncCommands for redis::cluster_async::ClusterConnection {
+    fn tvs_cluster_scan_index<K: FromRedisValue + Send>(
+        &mut self,
+    ) -> RedisFuture<'_, ClusterScanIndexAsync<'_, K>> {
+        Box::pin(async move { ClusterScanIndexAsync::new(self, None).await })
+    }
+
+    fn tvs_cluster_scan_index_match<'a, P: ToRedisArgs + Send + 'a, K: FromRedisValue + Send>(
+        &'a mut self,
+        pattern: P,
+    ) -> RedisFuture<'a, ClusterScanIndexAsync<'a, K>> {
+        Box::pin(
+            async move { ClusterScanIndexAsync::new(self, Some(pattern.to_redis_args())).await },
+        )
+    }
+}
+
+/// Async version of `ClusterScanIndex`, polled with `next_item`.
+#[cfg(feature = "cluster-async")]
+pub struct ClusterScanIndexAsync<'a, K> {
+    conn: &'a mut redis::cluster_async::ClusterConnection,
+    routes: std::vec::IntoIter<Route>,
+    // the current master, while its cursor is not 0
+    current: Option<Route>,
+    cursor: u64,
+    pattern: Option<Vec<Vec<u8>>>,
+    batch: std::vec::IntoIter<K>,
+}
+
+#[cfg(feature = "cluster-async")]
+impl<'a, K: FromRedisValue> ClusterScanIndexAsync<'a, K> {
+    async fn new(
+        conn: &'a mut redis::cluster_async::ClusterConnection,
+        pattern: Option<Vec<Vec<u8>>>,
+    ) -> RedisResult<Self> {
+        let slots = conn
+            .route_command(
+                redis::cmd("CLUSTER").arg("SLOTS"),
+                RoutingInfo::SingleNode(SingleNodeRoutingInfo::Random),
+            )
+            .await?;
+        // any slot of a master routes to it
+        let routes: Vec<Route> = master_slots(&slots)?
+            .values()
+            .map(|ranges| Route::new(ranges[0].0, SlotAddr::Master))
+            .collect();
+        Ok(ClusterScanIndexAsync {
+            conn,
+            routes: routes.into_iter(),
+            current: None,
+            cursor: 0,
+            pattern,
+            batch: vec![].into_iter(),
+        })
+    }
+
+    /// The next index name, `None` after the last master or an error.
+    pub async fn next_item(&mut self) -> Option<RedisResult<K>> {
+        loop {
+            if let Some(item) = self.batch.next() {
+                return Some(Ok(item));
+            }
+            let route = match self.current.take() {
+                Some(route) => route,
+                None => {
+                    self.cursor = 0;
+                    self.routes.next()?
+                }
+            };
+            let page = self
+                .conn
+                .route_command(
+                    &scan_index_cmd(self.cursor, self.pattern.as_ref()),
+                    RoutingInfo::SingleNode(SingleNodeRoutingInfo::SpecificNode(route)),
+                )
+                .await
+                .and_then(|page| redis::from_redis_value::<(u64, Vec<K>)>(&page));
+            match page {
+                Ok((next, items)) => {
+                    self.batch = items.into_iter();
+                    self.cursor = next;
+                    if next != 0 {
+                        self.current = Some(route);
+                    }
+                }
+                Err(e) => {
+                    self.routes = vec![].into_iter();
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+/// Whether `C` is one of the cluster connections of redis, or a `&mut` to
+/// one.
+///
+/// Every connection type shares the blanket impls of the command traits, so
+/// the type name is all there is to tell them apart. Wrappers such as pooled
+/// connections are not recognized.
+pub(crate) fn is_cluster_connection<C: ?Sized>() -> bool {
+    let name = type_name::<C>();
+    let name = name.strip_prefix("&mut ").unwrap_or(name);
+    #[cfg(feature = "cluster-async")]
+    if name == type_name::<redis::cluster_async::ClusterConnection>() {
+        return true;
+    }
+    name == type_name::<redis::cluster::ClusterConnection>()
+}
+
+fn scan_index_cmd<P: ToRedisArgs>(cursor: u64, pattern: Option<&P>) -> redis::Cmd {
+    let mut c = redis::cmd("TVS.SCANINDEX");
+    c.arg(cursor);
+    if let Some(p) = pattern {
+        c.arg("MATCH").arg(p);
+    }
+    c
+}
+
+/// Slot ranges of each master, keyed by host and port.
+type MasterSlots = BTreeMap<(String, u16), Vec<(u16, u16)>>;
+
+/// Parses a CLUSTER SLOTS reply.
+fn master_slots(value: &Value) -> RedisResult<MasterSlots> {
+    let invalid = || RedisError::from((ErrorKind::TypeError, "invalid CLUSTER SLOTS reply"));
+    let mut masters = MasterSlots::new();
+    for range in Vec::<Value>::from_redis_value(value)? {
+        let items: Vec<Value> = Vec::from_redis_value(&range)?;
+        if items.len() < 3 {
+            return Err(invalid());
+        }
+        let start: u16 = FromRedisValue::from_redis_value(&items[0])?;
+        let end: u16 = FromRedisValue::from_redis_value(&items[1])?;
+        let node: Vec<Value> = Vec::from_redis_value(&items[2])?;
+        if node.len() < 2 {
+            return Err(invalid());
+        }
+        let host: String = FromRedisValue::from_redis_value(&node[0])?;
+        let port: u16 = FromRedisValue::from_redis_value(&node[1])?;
+        masters.entry((host, port)).or_default().push((start, end));
+    }
+    Ok(masters)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockServer;
+    use crate::{TairVectorCommands, TairVectorPipeline};
+    use redis::cluster::ClusterClient;
+    use redis::cluster_routing::get_slot;
+    use redis::IntoConnectionInfo;
+
+    fn node(host: &str, port: i64) -> Value {
+        Value::Bulk(vec![
+            Value::Data(host.as_bytes().to_vec()),
+            Value::Int(port),
+            Value::Data(b"node-id".to_vec()),
+        ])
+    }
+
+    #[test]
+    fn parse_cluster_slots() {
+        let reply = Value::Bulk(vec![
+            Value::Bulk(vec![
+                Value::Int(0),
+                Value::Int(5460),
+                node("10.0.0.1", 6379),
+                node("10.0.0.4", 6379),
+            ]),
+            Value::Bulk(vec![
+                Value::Int(5461),
+                Value::Int(10922),
+                node("10.0.0.2", 6379),
+            ]),
+            Value::Bulk(vec![
+                Value::Int(10923),
+                Value::Int(16383),
+                node("10.0.0.1", 6379),
+            ]),
+        ]);
+        let masters = master_slots(&reply).unwrap();
+        assert_eq!(masters.len(), 2);
+        assert_eq!(
+            masters[&("10.0.0.1".to_owned(), 6379)],
+            vec![(0, 5460), (10923, 16383)]
+        );
+        assert_eq!(masters[&("10.0.0.2".to_owned(), 6379)], vec![(5461, 10922)]);
+    }
+
+    #[test]
+    fn fan_out() {
+        // two masters, each a mock server with its own indices
+        let servers = [MockServer::start().unwrap(), MockServer::start().unwrap()];
+        for (server, names) in servers.iter().zip([&["a1"][..], &["a2", "b1"]]) {
+            let mut conn = redis::Client::open(server.url())
+                .unwrap()
+                .get_connection()
+                .unwrap();
+            for name in names {
+                let _: bool = conn.tvs_create_index(*name, 2, "FLAT", "L2").unwrap();
+            }
+        }
+        let ranges = [(0, 8191), (8192, 16383)];
+        let slots = Value::Bulk(
+            servers
+                .iter()
+                .zip(ranges)
+                .map(|(server, (start, end))| {
+                    Value::Bulk(vec![
+                        Value::Int(start),
+                        Value::Int(end),
+                        node("127.0.0.1", server.addr().port() as i64),
+                    ])
+                })
+                .collect(),
+        );
+        let info = servers[0].url().into_connection_info().unwrap();
+
+        let scan = ClusterScanIndex::<String>::new(&slots, &info, None).unwrap();
+        let mut names: Vec<String> = scan.collect::<RedisResult<_>>().unwrap();
+        names.sort();
+        assert_eq!(names, ["a1", "a2", "b1"]);
+
+        let pattern = Some("a*".to_redis_args());
+        let scan = ClusterScanIndex::<String>::new(&slots, &info, pattern).unwrap();
+        let mut names: Vec<String> = scan.collect::<RedisResult<_>>().unwrap();
+        names.sort();
+        assert_eq!(names, ["a1", "a2"]);
+
+        // a master that cannot be reached ends the scan with its error
+        drop(servers);
+        let mut scan = ClusterScanIndex::<String>::new(&slots, &info, None).unwrap();
+        assert!(scan.next().unwrap().is_err());
+        assert!(scan.next().is_none());
+    }
+
+    /// One index name in the slots of each master of a two node mock cluster.
+    fn index_names(prefix: &str) -> [String; 2] {
+        let name = |half: u16| {
+            (0..)
+                .map(|i| format!("{}-{}", prefix, i))
+                .find(|name| get_slot(name.as_bytes()) / 8192 == half)
+                .unwrap()
+        };
+        [name(0), name(1)]
+    }
+
+    fn node_connection(server: &MockServer) -> redis::Connection {
+        redis::Client::open(server.url())
+            .unwrap()
+            .get_connection()
+            .unwrap()
+    }
+
+    #[test]
+    fn routing() {
+        let servers = MockServer::start_cluster(2).unwrap();
+        let urls: Vec<String> = servers.iter().map(MockServer::url).collect();
+        let mut conn = ClusterClient::new(urls).unwrap().get_connection().unwrap();
+        let names = index_names("routing");
+
+        for (i, name) in names.iter().enumerate() {
+            let _: bool = conn.tvs_create_index(name, 2, "FLAT", "L2").unwrap();
+            let _: usize = conn.tvs_hset_vector(name, "k1", [1.0f32, 0.0]).unwrap();
+            let _: Vec<usize> = redis::cluster::cluster_pipe()
+                .tvs_hset_vector(name, "k2", [0.0f32, 1.0])
+                .query(&mut conn)
+                .unwrap();
+            let hits: Vec<(String, f32)> = conn.tvs_knnsearch(name, 10, [1.0f32, 0.0]).unwrap();
+            assert_eq!(hits.len(), 2);
+            assert_eq!(hits[0].0, "k1");
+
+            // everything landed on the master owning the slot of the name
+            let mut owner = node_connection(&servers[i]);
+            let info = owner.tvs_get_index_info(name).unwrap().unwrap();
+            assert_eq!(info.data_count, 2);
+            let mut other = node_connection(&servers[1 - i]);
+            assert!(other.tvs_get_index_info(name).unwrap().is_none());
+        }
+
+        let params: &[(&str, usize)] = &[];
+        let err = conn
+            .tvs_mindex_knnsearch::<_, _, _, _, Value>(&names, 10, [1.0f32, 0.0], params)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ClientError);
+        let err = conn
+            .tvs_mindex_mknnsearch::<_, _, _, _, Value>(&names, 10, &[[1.0f32, 0.0]], params)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ClientError);
+        let result: RedisResult<Vec<Value>> = redis::cluster::cluster_pipe()
+            .tvs_mindex_knnsearch(&names, 10, [1.0f32, 0.0], params)
+            .query(&mut conn);
+        assert!(result.is_err());
+
+        let err = conn.tvs_scan_index::<String>().err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::ClientError);
+        let node = servers[0].url().into_connection_info().unwrap();
+        let mut scanned: Vec<String> = conn
+            .tvs_cluster_scan_index(&node)
+            .unwrap()
+            .collect::<RedisResult<_>>()
+            .unwrap();
+        scanned.sort();
+        assert_eq!(scanned, names);
+    }
+
+    #[cfg(feature = "cluster-async")]
+    #[tokio::test]
+    async fn async_scan() {
+        use crate::TairVectorAsyncCommands;
+
+        let servers = MockServer::start_cluster(2).unwrap();
+        let urls: Vec<String> = servers.iter().map(MockServer::url).collect();
+        let client = ClusterClient::new(urls).unwrap();
+        let mut conn = client.get_async_connection().await.unwrap();
+        let names = index_names("scan");
+        for name in names.iter().chain(&["unmatched".to_owned()]) {
+            let _: bool = conn.tvs_create_index(name, 2, "FLAT", "L2").await.unwrap();
+        }
+
+        let mut scan = conn.tvs_cluster_scan_index::<String>().await.unwrap();
+        let mut scanned = vec![];
+        while let Some(name) = scan.next_item().await {
+            scanned.push(name.unwrap());
+        }
+        scanned.sort();
+        assert_eq!(scanned, [&names[0], &names[1], "unmatched"]);
+
+        let mut scan = conn
+            .tvs_cluster_scan_index_match::<_, String>("scan-*")
+            .await
+            .unwrap();
+        let mut scanned = vec![];
+        while let Some(name) = scan.next_item().await {
+            scanned.push(name.unwrap());
+        }
+        scanned.sort();
+        assert_eq!(scanned, names);
+
+        let err = conn.tvs_scan_index::<String>().await.err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::ClientError);
+        let params: &[(&str, usize)] = &[];
+        let err = conn
+            .tvs_mindex_knnsearch::<_, _, _, _, Value>(&names, 10, [1.0f32, 0.0], params)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ClientError);
+    }
+}
@@ -0,0 +1,549 @@
+use crate::index::DIFF_FIELDS;
+use crate::SchemaDiff;
+use redis::{ErrorKind, RedisError};
+use std::error::Error;
+use std::fmt;
+#[cfg(feature = "aio")]
+use std::future::Future;
+#[cfg(feature = "aio")]
+use std::pin::Pin;
+
+/// Errors of this crate, with the common server failures told apart.
+///
+/// Convert a `RedisError` returned by any command with
+/// `TairVectorError::from` to branch on the failure, e.g. to treat an
+/// existing index as success:
+///
+/// ```no_run
+/// # use tair_vector_rs::{TairVectorCommands, TairVectorError};
+/// # fn create(conn: &mut redis::Connection) -> Result<(), TairVectorError> {
+/// match conn.tvs_create_index::<_, _, _, _, bool>("idx", 128, "HNSW", "L2") {
+///     Ok(_) => {}
+///     Err(e) => match TairVectorError::from(e) {
+///         TairVectorError::IndexAlreadyExists(_) => {}
+///         e => return Err(e),
+///     },
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub enum TairVectorError {
+    /// The index does not exist. Holds the index name or the server message.
+    IndexNotFound(String),
+    /// Holds the server message.
+    IndexAlreadyExists(String),
+    /// The vector does not have the dimension of the index. Either side is
+    /// `None` when it is not known, e.g. when reported by the server.
+    DimensionMismatch {
+        expected: Option<usize>,
+        actual: Option<usize>,
+    },
+    /// A malformed command or out of range parameter. Holds the server
+    /// message.
+    InvalidParameter(String),
+    /// The index cannot store vectors of this element type.
+    UnsupportedDataType(String),
+    /// An existing index differs from the requested `IndexSpec`.
+    SchemaMismatch(Vec<SchemaDiff>),
+    /// A reply that could not be decoded into the requested type.
+    Parse(RedisError),
+    /// Any other error, from the connection or the server.
+    Transport(RedisError),
+}
+
+/// `redis::RedisFuture` for commands that fail with a `TairVectorError`.
+#[cfg(feature = "aio")]
+pub type TairVectorFuture<'a, T> =
+    Pin<Box<dyn Future<Output = Result<T, TairVectorError>> + Send + 'a>>;
+
+impl fmt::Display for TairVectorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TairVectorError::IndexNotFound(detail) => write!(f, "index not found: {}", detail),
+            TairVectorError::IndexAlreadyExists(detail) => {
+                write!(f, "index already exists: {}", detail)
+            }
+            TairVectorError::DimensionMismatch { expected, actual } => {
+                f.write_str("dimension mismatch")?;
+                if let Some(expected) = expected {
+                    write!(f, ", expected {}", expected)?;
+                }
+                if let Some(actual) = actual {
+                    write!(f, ", got {}", actual)?;
+                }
+                Ok(())
+            }
+            TairVectorError::InvalidParameter(detail) => {
+                write!(f, "invalid parameter: {}", detail)
+            }
+            TairVectorError::UnsupportedDataType(detail) => {
+                write!(f, "unsupported data type: {}", detail)
+            }
+            TairVectorError::SchemaMismatch(diffs) => {
+                f.write_str("schema mismatch")?;
+                for (i, diff) in diffs.iter().enumerate() {
+                    f.write_str(if i == 0 { ": " } else { "; " })?;
+                    diff.fmt(f)?;
+                }
+                Ok(())
+            }
+            TairVectorError::Parse(e) | TairVectorError::Transport(e) => e.fmt(f),
+        }
+    }
+}
+
+impl Error for TairVectorError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TairVectorError::Parse(e) | TairVectorError::Transport(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Classifies server errors by their message. Errors that match no known
+/// failure are kept as `Transport`, arguments rejected by this crate before
+/// sending become `InvalidParameter`. Errors converted from a
+/// `TairVectorError` get their variant back.
+impl From<RedisError> for TairVectorError {
+    fn from(e: RedisError) -> Self {
+        match (e.kind(), e.detail()) {
+            (ErrorKind::TypeError, _) => return TairVectorError::Parse(e),
+            (ErrorKind::ClientError | ErrorKind::ResponseError, Some(detail)) => {
+                if let Some(decoded) = decode(detail) {
+                    return decoded;
+                }
+                if e.kind() == ErrorKind::ClientError {
+                    return TairVectorError::InvalidParameter(detail.to_owned());
+                }
+            }
+            (ErrorKind::ResponseError | ErrorKind::ExtensionError, _) => {}
+            _ => return TairVectorError::Transport(e),
+        }
+
+        let message = match (e.code(), e.detail()) {
+            (Some("ERR") | None, Some(detail)) => detail.to_owned(),
+            (Some(code), Some(detail)) => format!("{} {}", code, detail),
+            (code, None) => code.unwrap_or_default().to_owned(),
+        };
+        let text = message.to_ascii_lowercase();
+        let has = |words: &[&str]| words.iter().any(|w| text.contains(w));
+
+        // full phrases first, so that e.g. "invalid dimension" or "key not
+        // exists" do not end up as a dimension or index failure
+        if has(&["index already exist"]) {
+            TairVectorError::IndexAlreadyExists(message)
+        } else if has(&[
+            "index not exist",
+            "index not found",
+            "no such index",
+            "index does not exist",
+        ]) {
+            TairVectorError::IndexNotFound(message)
+        } else if has(&[
+            "dimension not match",
+            "dimension mismatch",
+            "dim not match",
+            "dim mismatch",
+        ]) {
+            TairVectorError::DimensionMismatch {
+                expected: number_after(&text, "expected"),
+                actual: number_after(&text, "got"),
+            }
+        } else if has(&["does not support data type", "unsupported data type"]) {
+            TairVectorError::UnsupportedDataType(message)
+        } else if has(&[
+            "invalid",
+            "illegal",
+            "syntax",
+            "wrong number of arguments",
+            "out of range",
+            "not a valid",
+            "must be",
+            "parameter",
+        ]) {
+            TairVectorError::InvalidParameter(message)
+        } else if has(&["already exist"]) {
+            TairVectorError::IndexAlreadyExists(message)
+        } else if has(&["dimension"]) {
+            TairVectorError::DimensionMismatch {
+                expected: number_after(&text, "expected"),
+                actual: number_after(&text, "got"),
+            }
+        } else if has(&["data type", "data_type", "datatype"]) {
+            TairVectorError::UnsupportedDataType(message)
+        } else {
+            TairVectorError::Transport(e)
+        }
+    }
+}
+
+/// The number following `word` in `text`, as in "expected 3, got 2".
+fn number_after(text: &str, word: &str) -> Option<usize> {
+    let rest = &text[text.find(word)? + word.len()..];
+    let rest = rest.trim_start_matches([' ', ':']);
+    let end = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    rest[..end].parse().ok()
+}
+
+/// Codes put before the detail of converted errors, one per variant.
+const INDEX_NOT_FOUND: &str = "INDEXNOTFOUND";
+const INDEX_EXISTS: &str = "INDEXEXISTS";
+const DIMENSION_MISMATCH: &str = "DIMMISMATCH";
+const INVALID_PARAMETER: &str = "INVALIDPARAM";
+const UNSUPPORTED_DATA_TYPE: &str = "UNSUPPORTEDTYPE";
+const SCHEMA_MISMATCH: &str = "SCHEMAMISMATCH";
+
+/// Converts back for callers working with `RedisResult`. The detail starts
+/// with a code naming the variant, e.g. `DIMMISMATCH expected 3, got 2`, so
+/// that `TairVectorError::from` returns the same variant.
+impl From<TairVectorError> for RedisError {
+    fn from(e: TairVectorError) -> Self {
+        let (kind, description, code, payload) = match e {
+            TairVectorError::Parse(e) | TairVectorError::Transport(e) => return e,
+            TairVectorError::IndexNotFound(detail) => (
+                ErrorKind::ResponseError,
+                "Index not found",
+                INDEX_NOT_FOUND,
+                detail,
+            ),
+            TairVectorError::IndexAlreadyExists(detail) => (
+                ErrorKind::ResponseError,
+                "Index already exists",
+                INDEX_EXISTS,
+                detail,
+            ),
+            TairVectorError::InvalidParameter(detail) => (
+                ErrorKind::ResponseError,
+                "Invalid parameter",
+                INVALID_PARAMETER,
+                detail,
+            ),
+            TairVectorError::DimensionMismatch { expected, actual } => {
+                let expected = expected.map(|n| format!("expected {}", n));
+                let actual = actual.map(|n| format!("got {}", n));
+                let payload = expected.into_iter().chain(actual).collect::<Vec<_>>();
+                (
+                    ErrorKind::ClientError,
+                    "Invalid vector",
+                    DIMENSION_MISMATCH,
+                    payload.join(", "),
+                )
+            }
+            TairVectorError::UnsupportedDataType(detail) => (
+                ErrorKind::ClientError,
+                "Invalid vector",
+                UNSUPPORTED_DATA_TYPE,
+                detail,
+            ),
+            TairVectorError::SchemaMismatch(diffs) => {
+                let payload = diffs.iter().map(|d| d.to_string()).collect::<Vec<_>>();
+                (
+                    ErrorKind::ClientError,
+                    "Index schema mismatch",
+                    SCHEMA_MISMATCH,
+                    payload.join("; "),
+                )
+            }
+        };
+        let detail = if payload.is_empty() {
+            code.to_owned()
+        } else {
+            format!("{} {}", code, payload)
+        };
+        RedisError::from((kind, description, detail))
+    }
+}
+
+/// The variant of an error detail written by `From<TairVectorError>`.
+fn decode(detail: &str) -> Option<TairVectorError> {
+    let (code, payload) = detail.split_once(' ').unwrap_or((detail, ""));
+    let payload = payload.to_owned();
+    Some(match code {
+        INDEX_NOT_FOUND => TairVectorError::IndexNotFound(payload),
+        INDEX_EXISTS => TairVectorError::IndexAlreadyExists(payload),
+        INVALID_PARAMETER => TairVectorError::InvalidParameter(payload),
+        UNSUPPORTED_DATA_TYPE => TairVectorError::UnsupportedDataType(payload),
+        DIMENSION_MISMATCH => TairVectorError::DimensionMismatch {
+            expected: number_after(&payload, "expected"),
+            actual: number_after(&payload, "got"),
+        },
+        SCHEMA_MISMATCH if payload.is_empty() => TairVectorError::SchemaMismatch(vec![]),
+        SCHEMA_MISMATCH => TairVectorError::SchemaMismatch(
+            payload
+                .split("; ")
+                .map(|diff| {
+                    let (field, rest) = diff.split_once(": expected ")?;
+                    let (expected, actual) = rest.rsplit_once(", got ")?;
+                    Some(SchemaDiff {
+                        field: DIFF_FIELDS.into_iter().find(|f| *f == field)?,
+                        expected: expected.to_owned(),
+                        actual: actual.to_owned(),
+                    })
+                })
+                .collect::<Option<_>>()?,
+        ),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DistanceType, IndexSpec, IndexType, TairVectorCommands};
+
+    fn server_error(detail: &str) -> TairVectorError {
+        RedisError::from((
+            ErrorKind::ResponseError,
+            "An error was signalled by the server",
+            detail.to_owned(),
+        ))
+        .into()
+    }
+
+    #[test]
+    fn classify() {
+        assert!(matches!(
+            server_error("index already exists"),
+            TairVectorError::IndexAlreadyExists(m) if m == "index already exists"
+        ));
+        assert!(matches!(
+            server_error("index not exists"),
+            TairVectorError::IndexNotFound(_)
+        ));
+        assert!(matches!(
+            server_error("dimension not match, expected 2, got 3"),
+            TairVectorError::DimensionMismatch {
+                expected: Some(2),
+                actual: Some(3)
+            }
+        ));
+        assert!(matches!(
+            server_error("vector dimension mismatch"),
+            TairVectorError::DimensionMismatch {
+                expected: None,
+                actual: None
+            }
+        ));
+        assert!(matches!(
+            server_error("distance method JACCARD does not support data type FLOAT32"),
+            TairVectorError::UnsupportedDataType(_)
+        ));
+        assert!(matches!(
+            server_error("invalid dimension: 0"),
+            TairVectorError::InvalidParameter(_)
+        ));
+        assert!(matches!(
+            server_error("elements must be 0 or 1 for data type BINARY"),
+            TairVectorError::InvalidParameter(_)
+        ));
+        assert!(matches!(
+            server_error("key not exists"),
+            TairVectorError::Transport(_)
+        ));
+        assert!(matches!(
+            server_error("unknown command 'TVS.FOO'"),
+            TairVectorError::Transport(_)
+        ));
+
+        // errors without the ERR prefix are extension errors in redis-rs
+        let e = redis::parse_redis_value(b"-INDEX not found\r\n").unwrap_err();
+        assert!(matches!(
+            TairVectorError::from(e),
+            TairVectorError::IndexNotFound(m) if m == "INDEX not found"
+        ));
+
+        let e = RedisError::from((ErrorKind::TypeError, "Invalid vector", "x".to_owned()));
+        assert!(matches!(
+            TairVectorError::from(e),
+            TairVectorError::Parse(_)
+        ));
+        let e = RedisError::from(std::io::Error::from(std::io::ErrorKind::ConnectionReset));
+        assert!(matches!(
+            TairVectorError::from(e),
+            TairVectorError::Transport(_)
+        ));
+
+        let e = RedisError::from(TairVectorError::DimensionMismatch {
+            expected: Some(3),
+            actual: Some(2),
+        });
+        assert_eq!(e.kind(), ErrorKind::ClientError);
+        assert!(e.to_string().contains("expected 3, got 2"));
+
+        // rejected before sending
+        let err = crate::Vector::new(vec![f32::NAN]).unwrap_err();
+        assert!(matches!(
+            TairVectorError::from(err),
+            TairVectorError::InvalidParameter(m) if m == "element 0 is not finite: NaN"
+        ));
+    }
+
+    #[test]
+    fn round_trip() {
+        let diffs = vec![
+            SchemaDiff {
+                field: "dimension",
+                expected: "3".to_owned(),
+                actual: "2".to_owned(),
+            },
+            SchemaDiff {
+                field: "params",
+                expected: "ef_search 100".to_owned(),
+                actual: "ef_search unset".to_owned(),
+            },
+        ];
+        let errors = vec![
+            TairVectorError::IndexNotFound("idx".to_owned()),
+            TairVectorError::IndexAlreadyExists("index already exists".to_owned()),
+            TairVectorError::DimensionMismatch {
+                expected: Some(3),
+                actual: Some(2),
+            },
+            TairVectorError::DimensionMismatch {
+                expected: None,
+                actual: Some(2),
+            },
+            TairVectorError::DimensionMismatch {
+                expected: None,
+                actual: None,
+            },
+            TairVectorError::InvalidParameter("element 0 is not finite: NaN".to_owned()),
+            TairVectorError::UnsupportedDataType("BINARY, index stores FLOAT32".to_owned()),
+            TairVectorError::SchemaMismatch(diffs),
+            TairVectorError::SchemaMismatch(vec![]),
+        ];
+        for error in errors {
+            let expected = format!("{:?}", error);
+            let back = TairVectorError::from(RedisError::from(error));
+            assert_eq!(format!("{:?}", back), expected);
+        }
+
+        let e = RedisError::from((ErrorKind::TypeError, "Invalid vector", "x".to_owned()));
+        assert!(matches!(
+            TairVectorError::from(RedisError::from(TairVectorError::Parse(e))),
+            TairVectorError::Parse(e) if e.detail() == Some("x")
+        ));
+        let e = RedisError::from(std::io::Error::from(std::io::ErrorKind::ConnectionReset));
+        assert!(matches!(
+            TairVectorError::from(RedisError::from(TairVectorError::Transport(e))),
+            TairVectorError::Transport(e) if e.is_io_error()
+        ));
+    }
+
+    #[test]
+    fn tair_replies() {
+        // whole error replies as Tair sends them, parsed like any reply
+        let replies: &[(&[u8], &str)] = &[
+            (b"-ERR index not exists\r\n", "IndexNotFound"),
+            (b"-ERR index already exists\r\n", "IndexAlreadyExists"),
+            (
+                b"-ERR dimension not match, expected 128, got 64\r\n",
+                "DimensionMismatch { expected: Some(128), actual: Some(64) }",
+            ),
+            (
+                b"-ERR distance method JACCARD does not support data type FLOAT32\r\n",
+                "UnsupportedDataType",
+            ),
+            (b"-ERR invalid dimension: 0\r\n", "InvalidParameter"),
+            (b"-ERR syntax error\r\n", "InvalidParameter"),
+            (
+                b"-ERR wrong number of arguments for 'tvs.hset' command\r\n",
+                "InvalidParameter",
+            ),
+            (b"-ERR unknown command 'TVS.FOO'\r\n", "Transport"),
+            (
+                b"-WRONGTYPE Operation against a key holding the wrong kind of value\r\n",
+                "Transport",
+            ),
+            (b"-INDEX not found\r\n", "IndexNotFound"),
+        ];
+        for (reply, expected) in replies {
+            let e = redis::parse_redis_value(reply).unwrap_err();
+            let debug = format!("{:?}", TairVectorError::from(e));
+            assert!(
+                debug.starts_with(expected),
+                "{} classified as {}",
+                String::from_utf8_lossy(reply).trim_end(),
+                debug
+            );
+        }
+    }
+
+    #[test]
+    fn server_errors() {
+        let redis_url = crate::mock::test_url();
+
+        let index_name = "test-server-errors";
+        let client = redis::Client::open(redis_url).unwrap();
+        let mut conn = client.get_connection().unwrap();
+
+        // cleanup
+        conn.tvs_del_index::<_, usize>(index_name).unwrap();
+
+        let err = conn
+            .tvs_hset_vector::<_, _, _, usize>(index_name, "k1", [1.0f32, 2.0])
+            .unwrap_err();
+        assert!(matches!(
+            TairVectorError::from(err),
+            TairVectorError::IndexNotFound(_)
+        ));
+
+        let spec = IndexSpec::new(index_name, 0, IndexType::Hnsw, DistanceType::L2);
+        let err = conn.tvs_create_index_spec::<bool>(&spec).unwrap_err();
+        assert!(matches!(
+            TairVectorError::from(err),
+            TairVectorError::InvalidParameter(_)
+        ));
+
+        let spec = IndexSpec::new(index_name, 2, IndexType::Flat, DistanceType::Jaccard);
+        let err = conn.tvs_create_index_spec::<bool>(&spec).unwrap_err();
+        assert!(matches!(
+            TairVectorError::from(err),
+            TairVectorError::UnsupportedDataType(_)
+        ));
+
+        let spec = IndexSpec::new(index_name, 2, IndexType::Hnsw, DistanceType::L2);
+        let created: bool = conn.tvs_create_index_spec(&spec).unwrap();
+        assert!(created);
+        let err = conn.tvs_create_index_spec::<bool>(&spec).unwrap_err();
+        assert!(matches!(
+            TairVectorError::from(err),
+            TairVectorError::IndexAlreadyExists(_)
+        ));
+
+        let err = conn
+            .tvs_hset_vector::<_, _, _, usize>(index_name, "k1", [1.0f32, 2.0, 3.0])
+            .unwrap_err();
+        assert!(matches!(
+            TairVectorError::from(err),
+            TairVectorError::DimensionMismatch {
+                expected: Some(2),
+                actual: Some(3)
+            }
+        ));
+
+        let err = conn
+            .tvs_scan_filter::<_, _, String>(index_name, "price >")
+            .map(|iter| iter.count())
+            .unwrap_err();
+        assert!(matches!(
+            TairVectorError::from(err),
+            TairVectorError::InvalidParameter(_)
+        ));
+
+        let err = redis::cmd("TVS.HSET")
+            .arg(index_name)
+            .query::<usize>(&mut conn)
+            .unwrap_err();
+        assert!(matches!(
+            TairVectorError::from(err),
+            TairVectorError::InvalidParameter(_)
+        ));
+
+        conn.tvs_del_index::<_, usize>(index_name).unwrap();
+    }
+}
@@ -0,0 +1,639 @@
+use crate::invalid_argument;
+use redis::{RedisResult, RedisWrite, ToRedisArgs};
+use std::fmt;
+use std::ops::{BitAnd, BitOr, Not};
+
+/// A FILTER expression over the attributes of an index.
+///
+/// Built with `field`, combined with `&` (`&&`), `|` (`||`) and `!`, and
+/// rendered with the parentheses and string escaping TairVector expects.
+/// Accepted wherever a command takes a filter.
+///
+/// A comparison on a field name that is not an identifier, or with a NaN or
+/// infinite number, fails the command with an `InvalidParameter` error
+/// before it is sent. In a pipeline, it is rendered as an invalid expression
+/// so that the server rejects the filter.
+///
+/// ```
+/// use tair_vector_rs::field;
+///
+/// let filter = (field("price").gt(10) | field("name").eq("say \"hi\"")) & !field("sold").eq(true);
+/// assert_eq!(
+///     filter.to_string(),
+///     r#"(price > 10 || name == "say \"hi\"") && !(sold == true)"#
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    Compare {
+        field: String,
+        op: CompareOp,
+        value: FilterValue,
+    },
+    /// Matches if the field equals any of the values. An empty set matches
+    /// nothing and is rendered as `false`.
+    In {
+        field: String,
+        values: Vec<FilterValue>,
+    },
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl CompareOp {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CompareOp::Eq => "==",
+            CompareOp::Ne => "!=",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+        }
+    }
+}
+
+/// A constant on the right hand side of a comparison.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterValue {
+    Int(i64),
+    /// Integers above `i64::MAX`.
+    UInt(u64),
+    /// Rendered as the shortest decimal that parses back to the same value,
+    /// without exponent.
+    Float(f64),
+    Bool(bool),
+    /// Rendered in double quotes, with `"`, `\` and line breaks escaped.
+    Str(String),
+}
+
+macro_rules! impl_from_int {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for FilterValue {
+                fn from(v: $t) -> Self {
+                    FilterValue::Int(v as i64)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_int!(i8, i16, i32, i64, u8, u16, u32);
+
+impl From<u64> for FilterValue {
+    fn from(v: u64) -> Self {
+        match i64::try_from(v) {
+            Ok(v) => FilterValue::Int(v),
+            Err(_) => FilterValue::UInt(v),
+        }
+    }
+}
+
+impl From<usize> for FilterValue {
+    fn from(v: usize) -> Self {
+        FilterValue::from(v as u64)
+    }
+}
+
+/// Keeps the f32 precision: `0.1f32` is sent as `0.1`, not as the f64
+/// closest to it.
+impl From<f32> for FilterValue {
+    fn from(v: f32) -> Self {
+        FilterValue::Float(v.to_string().parse().unwrap_or(v as f64))
+    }
+}
+
+impl From<f64> for FilterValue {
+    fn from(v: f64) -> Self {
+        FilterValue::Float(v)
+    }
+}
+
+impl From<bool> for FilterValue {
+    fn from(v: bool) -> Self {
+        FilterValue::Bool(v)
+    }
+}
+
+impl From<&str> for FilterValue {
+    fn from(v: &str) -> Self {
+        FilterValue::Str(v.to_owned())
+    }
+}
+
+impl From<String> for FilterValue {
+    fn from(v: String) -> Self {
+        FilterValue::Str(v)
+    }
+}
+
+impl fmt::Display for FilterValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterValue::Int(v) => write!(f, "{}", v),
+            FilterValue::UInt(v) => write!(f, "{}", v),
+            // `{}` never uses an exponent
+            FilterValue::Float(v) if v.fract() == 0.0 => write!(f, "{}.0", v),
+            FilterValue::Float(v) => write!(f, "{}", v),
+            FilterValue::Bool(v) => write!(f, "{}", v),
+            FilterValue::Str(v) => {
+                f.write_str("\"")?;
+                for c in v.chars() {
+                    match c {
+                        '"' | '\\' => write!(f, "\\{}", c)?,
+                        '\n' => f.write_str("\\n")?,
+                        '\r' => f.write_str("\\r")?,
+                        '\t' => f.write_str("\\t")?,
+                        c => write!(f, "{}", c)?,
+                    }
+                }
+                f.write_str("\"")
+            }
+        }
+    }
+}
+
+/// The left hand side of a comparison, see `field`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field(String);
+
+/// Starts a comparison on the attribute `name`, which must be an
+/// identifier: letters, digits and `_`, not starting with a digit.
+pub fn field(name: &str) -> Field {
+    Field(name.to_owned())
+}
+
+impl Field {
+    fn compare<V: Into<FilterValue>>(self, op: CompareOp, value: V) -> Filter {
+        Filter::Compare {
+            field: self.0,
+            op,
+            value: value.into(),
+        }
+    }
+
+    pub fn eq<V: Into<FilterValue>>(self, value: V) -> Filter {
+        self.compare(CompareOp::Eq, value)
+    }
+
+    pub fn ne<V: Into<FilterValue>>(self, value: V) -> Filter {
+        self.compare(CompareOp::Ne, value)
+    }
+
+    pub fn gt<V: Into<FilterValue>>(self, value: V) -> Filter {
+        self.compare(CompareOp::Gt, value)
+    }
+
+    pub fn ge<V: Into<FilterValue>>(self, value: V) -> Filter {
+        self.compare(CompareOp::Ge, value)
+    }
+
+    pub fn lt<V: Into<FilterValue>>(self, value: V) -> Filter {
+        self.compare(CompareOp::Lt, value)
+    }
+
+    pub fn le<V: Into<FilterValue>>(self, value: V) -> Filter {
+        self.compare(CompareOp::Le, value)
+    }
+
+    pub fn in_set<I: IntoIterator<Item = V>, V: Into<FilterValue>>(self, values: I) -> Filter {
+        Filter::In {
+            field: self.0,
+            values: values.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl Filter {
+    pub fn and(self, other: Filter) -> Filter {
+        Filter::And(Box::new(self), Box::new(other))
+    }
+
+    pub fn or(self, other: Filter) -> Filter {
+        Filter::Or(Box::new(self), Box::new(other))
+    }
+
+    /// Writes `self` as an operand of `&&` or `||`, adding parentheses
+    /// where the operator would otherwise bind differently.
+    fn fmt_operand(&self, f: &mut fmt::Formatter<'_>, parent: Precedence) -> fmt::Result {
+        if self.precedence() < parent {
+            write!(f, "({})", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+
+    /// Rejects field names that are not identifiers and NaN or infinite
+    /// numbers.
+    pub fn check(&self) -> RedisResult<()> {
+        let invalid = |detail| invalid_argument("Invalid filter", detail);
+        match self {
+            Filter::Compare { field, value, .. } => check_compare(field, [value]).map_err(invalid),
+            Filter::In { field, values } => check_compare(field, values).map_err(invalid),
+            Filter::And(l, r) | Filter::Or(l, r) => l.check().and_then(|_| r.check()),
+            Filter::Not(inner) => inner.check(),
+        }
+    }
+
+    fn precedence(&self) -> Precedence {
+        match self {
+            Filter::Or(..) => Precedence::Or,
+            Filter::In { values, .. } if values.len() > 1 => Precedence::Or,
+            Filter::And(..) => Precedence::And,
+            Filter::Compare { .. } | Filter::In { .. } | Filter::Not(_) => Precedence::Atom,
+        }
+    }
+}
+
+/// The reason a comparison cannot be rendered, if any.
+fn check_compare<'a, I: IntoIterator<Item = &'a FilterValue>>(
+    field: &str,
+    values: I,
+) -> Result<(), String> {
+    let mut chars = field.chars();
+    let identifier = chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_');
+    if !identifier {
+        return Err(format!(
+            "field name {} is not an identifier",
+            FilterValue::Str(field.to_owned())
+        ));
+    }
+    match values.into_iter().find_map(|value| match value {
+        FilterValue::Float(v) if !v.is_finite() => Some(v),
+        _ => None,
+    }) {
+        Some(v) => Err(format!("{} is compared with {}", field, v)),
+        None => Ok(()),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Precedence {
+    Or,
+    And,
+    Atom,
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let invalid = match self {
+            Filter::Compare { field, value, .. } => check_compare(field, [value]).err(),
+            Filter::In { field, values } => check_compare(field, values).err(),
+            _ => None,
+        };
+        if let Some(detail) = invalid {
+            // not valid filter syntax, the string keeps the field name from
+            // being parsed as part of the expression
+            return write!(f, "invalid filter {}", FilterValue::Str(detail));
+        }
+
+        match self {
+            Filter::Compare { field, op, value } => {
+                write!(f, "{} {} {}", field, op.as_str(), value)
+            }
+            Filter::In { values, .. } if values.is_empty() => f.write_str("false"),
+            Filter::In { field, values } => {
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(" || ")?;
+                    }
+                    write!(f, "{} == {}", field, value)?;
+                }
+                Ok(())
+            }
+            Filter::And(l, r) => {
+                l.fmt_operand(f, Precedence::And)?;
+                f.write_str(" && ")?;
+                r.fmt_operand(f, Precedence::And)
+            }
+            Filter::Or(l, r) => {
+                l.fmt_operand(f, Precedence::Or)?;
+                f.write_str(" || ")?;
+                r.fmt_operand(f, Precedence::Or)
+            }
+            Filter::Not(inner) => write!(f, "!({})", inner),
+        }
+    }
+}
+
+/// Anything that can be passed as the filter argument of a command: a
+/// `Filter`, checked before the command is sent, or an expression string,
+/// sent as is.
+pub trait AsFilter: ToRedisArgs {
+    /// Rejects filters that cannot be rendered.
+    fn check(&self) -> RedisResult<()>;
+}
+
+impl AsFilter for Filter {
+    fn check(&self) -> RedisResult<()> {
+        Filter::check(self)
+    }
+}
+
+impl AsFilter for &str {
+    fn check(&self) -> RedisResult<()> {
+        Ok(())
+    }
+}
+
+impl AsFilter for String {
+    fn check(&self) -> RedisResult<()> {
+        Ok(())
+    }
+}
+
+impl<T: AsFilter> AsFilter for &T {
+    fn check(&self) -> RedisResult<()> {
+        (**self).check()
+    }
+}
+
+/// Checks an optional filter argument.
+pub(crate) fn check_filter<F: AsFilter>(filter: &Option<F>) -> RedisResult<()> {
+    filter.as_ref().map_or(Ok(()), AsFilter::check)
+}
+
+impl ToRedisArgs for Filter {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        out.write_arg_fmt(self);
+    }
+}
+
+impl BitAnd for Filter {
+    type Output = Filter;
+
+    fn bitand(self, rhs: Filter) -> Filter {
+        self.and(rhs)
+    }
+}
+
+impl BitOr for Filter {
+    type Output = Filter;
+
+    fn bitor(self, rhs: Filter) -> Filter {
+        self.or(rhs)
+    }
+}
+
+impl Not for Filter {
+    type Output = Filter;
+
+    fn not(self) -> Filter {
+        Filter::Not(Box::new(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        DistanceType, IndexSpec, IndexType, KnnResult, TairVectorCommands, TairVectorPipeline,
+        Vector,
+    };
+    use redis::{ErrorKind, Iter, Value};
+
+    #[test]
+    fn render() {
+        assert_eq!(field("price").gt(10).to_string(), "price > 10");
+        assert_eq!(field("price").le(9.5).to_string(), "price <= 9.5");
+        assert_eq!(field("price").ge(1.0f32).to_string(), "price >= 1.0");
+        assert_eq!(field("price").lt(0.1f32).to_string(), "price < 0.1");
+        assert_eq!(field("price").gt(1e-7).to_string(), "price > 0.0000001");
+        assert_eq!(
+            field("price").lt(1e21).to_string(),
+            "price < 1000000000000000000000.0"
+        );
+        assert_eq!(
+            field("id").eq(u64::MAX).to_string(),
+            "id == 18446744073709551615"
+        );
+        assert_eq!(field("count").ge(3usize).to_string(), "count >= 3");
+        assert_eq!(field("价格").gt(1).to_string(), "价格 > 1");
+        assert_eq!(field("sold").ne(false).to_string(), "sold != false");
+        assert_eq!(
+            (field("a").eq(1) & field("b").eq(2) & field("c").eq(3)).to_string(),
+            "a == 1 && b == 2 && c == 3"
+        );
+        assert_eq!(
+            (field("a").eq(1) | field("b").eq(2) & field("c").eq(3)).to_string(),
+            "a == 1 || b == 2 && c == 3"
+        );
+        assert_eq!(
+            ((field("a").eq(1) | field("b").eq(2)) & field("c").eq(3)).to_string(),
+            "(a == 1 || b == 2) && c == 3"
+        );
+        assert_eq!(
+            field("a")
+                .eq(1)
+                .and(field("b").eq(2).or(field("c").eq(3)))
+                .to_string(),
+            "a == 1 && (b == 2 || c == 3)"
+        );
+        assert_eq!(
+            (!(field("a").eq(1) & field("b").lt(2))).to_string(),
+            "!(a == 1 && b < 2)"
+        );
+        assert_eq!(
+            (field("price").gt(0) & field("tag").in_set(["x", "y"])).to_string(),
+            r#"price > 0 && (tag == "x" || tag == "y")"#
+        );
+        assert_eq!(field("tag").in_set([7]).to_string(), "tag == 7");
+        assert_eq!(
+            (field("price").gt(0) & field("tag").in_set(Vec::<i32>::new())).to_string(),
+            "price > 0 && false"
+        );
+        assert_eq!(field("a").eq(1).to_redis_args(), vec![b"a == 1".to_vec()]);
+    }
+
+    #[test]
+    fn escaping() {
+        let eq = |s: &str| field("name").eq(s).to_string();
+        assert_eq!(eq("plain"), r#"name == "plain""#);
+        assert_eq!(eq(""), r#"name == """#);
+        assert_eq!(eq(r#"say "hi""#), r#"name == "say \"hi\"""#);
+        assert_eq!(eq(r"C:\dir\"), r#"name == "C:\\dir\\""#);
+        assert_eq!(eq(r#"\""#), r#"name == "\\\"""#);
+        assert_eq!(eq("a && b || !c"), r#"name == "a && b || !c""#);
+        assert_eq!(eq("it's (ok)"), r#"name == "it's (ok)""#);
+        assert_eq!(eq("多语言 ✓"), r#"name == "多语言 ✓""#);
+        assert_eq!(eq("line\nbreak"), r#"name == "line\nbreak""#);
+        assert_eq!(eq("a\r\tb"), r#"name == "a\r\tb""#);
+        assert_eq!(
+            field("name").in_set([r#"a"b"#, r"c\"]).to_string(),
+            r#"name == "a\"b" || name == "c\\""#
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        let filter = field("price").gt(f64::NAN);
+        assert_eq!(
+            filter.to_string(),
+            r#"invalid filter "price is compared with NaN""#
+        );
+        assert!(filter.check().is_err());
+        assert!(field("price").in_set([1.0, f64::INFINITY]).check().is_err());
+
+        let filter = field("x").eq(1) & field("a == 1 || b").eq(1);
+        assert_eq!(
+            filter.to_string(),
+            r#"x == 1 && invalid filter "field name \"a == 1 || b\" is not an identifier""#
+        );
+        let err = filter.check().unwrap_err();
+        assert_eq!(
+            err.detail(),
+            Some(r#"field name "a == 1 || b" is not an identifier"#)
+        );
+        for name in ["", "1a", "a b", "a\"", "a)"] {
+            assert!(field(name).eq(1).check().is_err(), "{:?}", name);
+        }
+        assert!((field("_a1").eq("x") & !field("b").in_set([1, 2]))
+            .check()
+            .is_ok());
+    }
+
+    #[test]
+    fn filter_commands() {
+        let redis_url = crate::mock::test_url();
+
+        let index_name = "test-filter-commands";
+        let client = redis::Client::open(redis_url).unwrap();
+        let mut conn = client.get_connection().unwrap();
+
+        // cleanup
+        conn.tvs_del_index::<_, usize>(index_name).unwrap();
+
+        let spec = IndexSpec::new(index_name, 2, IndexType::Flat, DistanceType::L2);
+        let created: bool = conn.tvs_create_index_spec(&spec).unwrap();
+        assert!(created);
+
+        for (key, vector, price, name) in [
+            ("k1", [1.0f32, 0.0], "5", r#"a "quoted" name"#),
+            ("k2", [2.0, 0.0], "15", r"back\slash"),
+            ("k3", [3.0, 0.0], "25", "plain"),
+        ] {
+            let vector = Vector::new(vector.to_vec()).unwrap().to_string();
+            let _: usize = conn
+                .tvs_hset_multi(
+                    index_name,
+                    key,
+                    &[
+                        ("VECTOR", vector.as_str()),
+                        ("price", price),
+                        ("name", name),
+                    ],
+                )
+                .unwrap();
+        }
+
+        let filter = field("price").gt(10) & !field("name").eq("plain");
+        let iter: Iter<String> = conn.tvs_scan_filter(index_name, &filter).unwrap();
+        assert_eq!(iter.collect::<Vec<_>>(), ["k2"]);
+
+        // invalid filters fail every command before it is sent, and are
+        // rejected by the server in a pipeline
+        let filter = field("price").gt(10.5f32) & field("price").lt(f64::NAN);
+        let err = conn
+            .tvs_scan_filter::<_, _, String>(index_name, &filter)
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), ErrorKind::ClientError);
+        let err = conn
+            .tvs_scan_full::<_, _, _, _, _, String>(
+                index_name,
+                None::<&str>,
+                None::<([f32; 2], f32)>,
+                Some(field("a b").eq(1)),
+            )
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), ErrorKind::ClientError);
+        let err = conn
+            .tvs_get_distance::<_, _, _, _, Value>(
+                index_name,
+                [0.0f32, 0.0],
+                &["k1"],
+                None,
+                None,
+                Some(&filter),
+            )
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ClientError);
+        let err = conn
+            .tvs_mknnsearch_with_params::<_, _, _, _, _, Value>(
+                index_name,
+                3,
+                &[[0.0f32, 0.0]],
+                Some(&filter),
+                &[] as &[(&str, &str)],
+            )
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ClientError);
+        let result: RedisResult<Vec<Value>> = redis::pipe()
+            .tvs_get_distance(
+                index_name,
+                [0.0f32, 0.0],
+                &["k1"],
+                None,
+                None,
+                Some(&filter),
+            )
+            .query(&mut conn);
+        assert!(result.is_err());
+
+        let filter = field("name").in_set([r#"a "quoted" name"#, r"back\slash"]);
+        let iter: Iter<String> = conn
+            .tvs_scan_full(
+                index_name,
+                None::<&str>,
+                None::<([f32; 2], f32)>,
+                Some(&filter),
+            )
+            .unwrap();
+        let mut keys: Vec<String> = iter.collect();
+        keys.sort();
+        assert_eq!(keys, ["k1", "k2"]);
+
+        let results: KnnResult = conn
+            .tvs_get_distance(
+                index_name,
+                [0.0f32, 0.0],
+                &["k1", "k2", "k3"],
+                None,
+                None,
+                Some(field("price").le(15)),
+            )
+            .unwrap();
+        assert_eq!(results.len(), 2);
+
+        let results: Vec<KnnResult> = conn
+            .tvs_mknnsearch_with_params(
+                index_name,
+                3,
+                &[Vector::new(vec![0.0, 0.0]).unwrap()],
+                Some(field("price").ge(25)),
+                &[] as &[(&str, &str)],
+            )
+            .unwrap();
+        assert_eq!(results[0].len(), 1);
+        assert_eq!(results[0][0].key, "k3");
+
+        conn.tvs_del_index::<_, usize>(index_name).unwrap();
+    }
+}
@@ -0,0 +1,193 @@
+use crate::{
+    check_elements, parse_elements, parse_reply, AsVector, DataType, VectorElement, VectorFormat,
+};
+use half::{bf16, f16};
+use redis::{FromRedisValue, RedisError, RedisResult, RedisWrite, ToRedisArgs, Value};
+use std::fmt;
+use std::str;
+
+impl VectorElement for f16 {
+    const DATA_TYPE: DataType = DataType::Float16;
+
+    #[inline]
+    fn to_f32(self) -> f32 {
+        f16::to_f32(self)
+    }
+}
+
+/// TairVector has no bfloat16 type, values are sent as decimals to FLOAT16
+/// indices. Values beyond the f16 range, which the server would store as
+/// infinity or zero, are rejected.
+impl VectorElement for bf16 {
+    const DATA_TYPE: DataType = DataType::Float16;
+
+    #[inline]
+    fn to_f32(self) -> f32 {
+        bf16::to_f32(self)
+    }
+
+    fn invalid(self) -> Option<&'static str> {
+        let x = self.to_f32();
+        let half = f16::from_f32(x);
+        if !x.is_finite() {
+            Some("not finite")
+        } else if half.is_infinite() || (half == f16::ZERO && x != 0.0) {
+            Some("out of the FLOAT16 range")
+        } else {
+            None
+        }
+    }
+}
+
+/// A vector of 16-bit floats, for `data_type FLOAT16` indices.
+///
+/// Elements are parsed straight into `T`, so values read back from the server
+/// compare equal to the ones written.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Vector16<T: VectorElement = f16>(Vec<T>);
+
+impl<T: VectorElement> Vector16<T> {
+    pub fn new(values: Vec<T>) -> RedisResult<Self> {
+        check_elements(values.iter())?;
+        Ok(Vector16(values))
+    }
+
+    pub fn from_slice(values: &[T]) -> RedisResult<Self> {
+        Vector16::new(values.to_vec())
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> Vec<T> {
+        self.0
+    }
+}
+
+impl<T: VectorElement> fmt::Display for Vector16<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        VectorFormat(self.0.iter()).fmt(f)
+    }
+}
+
+impl<T: VectorElement> ToRedisArgs for Vector16<T> {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        out.write_arg_fmt(VectorFormat(self.0.iter()));
+    }
+}
+
+impl<T: VectorElement> AsVector for Vector16<T> {
+    fn fmt_vector(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt_vector(f)
+    }
+
+    fn check(&self) -> RedisResult<()> {
+        self.0.check()
+    }
+
+    fn dim(&self) -> usize {
+        self.0.len()
+    }
+
+    fn data_type(&self) -> DataType {
+        T::DATA_TYPE
+    }
+}
+
+impl<T: VectorElement> str::FromStr for Vector16<T>
+where
+    T::Err: fmt::Display,
+{
+    type Err = RedisError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_elements(s).map(Vector16)
+    }
+}
+
+impl<T: VectorElement> FromRedisValue for Vector16<T>
+where
+    T::Err: fmt::Display,
+{
+    fn from_redis_value(value: &Value) -> RedisResult<Self> {
+        parse_reply(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DataType, DistanceType, IndexSpec, IndexType, TairVectorCommands};
+
+    #[test]
+    fn encode_half() {
+        let v = Vector16::new(vec![f16::from_f32(1.0), f16::from_f32(-0.5), f16::MAX]).unwrap();
+        assert_eq!(v.to_string(), "[1,-0.5,65504]");
+        assert_eq!(v.to_redis_args(), vec![b"[1,-0.5,65504]".to_vec()]);
+        assert!(Vector16::new(vec![f16::NAN]).is_err());
+        assert!(Vector16::from_slice(&[bf16::INFINITY]).is_err());
+
+        // bf16 values must fit FLOAT16
+        let err = Vector16::from_slice(&[bf16::ONE, bf16::from_f32(1e5)]).unwrap_err();
+        assert_eq!(
+            err.detail(),
+            Some("element 1 is out of the FLOAT16 range: 99840")
+        );
+        assert!(Vector16::from_slice(&[bf16::from_f32(1e-9)]).is_err());
+        assert!([bf16::from_f32(-1e6)].check().is_err());
+        assert!(Vector16::from_slice(&[bf16::from_f32(65280.0), bf16::ZERO]).is_ok());
+
+        // every finite f16 survives a round trip through the wire format
+        for bits in 0..=u16::MAX {
+            let x = f16::from_bits(bits);
+            if !x.is_finite() {
+                continue;
+            }
+            let v = Vector16::new(vec![x]).unwrap();
+            let decoded: Vector16 =
+                redis::from_redis_value(&Value::Data(v.to_redis_args().pop().unwrap())).unwrap();
+            assert_eq!(decoded.as_slice()[0].to_bits(), bits);
+        }
+
+        let decoded: Vector16<bf16> = "[1.5, 2]".parse().unwrap();
+        assert_eq!(
+            decoded.into_inner(),
+            vec![bf16::from_f32(1.5), bf16::from_f32(2.0)]
+        );
+        assert!("[1,x]".parse::<Vector16>().is_err());
+    }
+
+    #[test]
+    fn float16_index() {
+        let redis_url = crate::mock::test_url();
+
+        let index_name = "test-float16-index";
+        let client = redis::Client::open(redis_url).unwrap();
+        let mut conn = client.get_connection().unwrap();
+
+        // cleanup
+        conn.tvs_del_index::<_, usize>(index_name).unwrap();
+
+        let spec = IndexSpec::new(index_name, 2, IndexType::Hnsw, DistanceType::L2)
+            .data_type(DataType::Float16);
+        let created: bool = conn.tvs_create_index_spec(&spec).unwrap();
+        assert!(created);
+
+        let v1 = Vector16::from_slice(&[f16::from_f32(1.0), f16::from_f32(0.5)]).unwrap();
+        let v2 = Vector16::from_slice(&[f16::from_f32(-1.0), f16::from_f32(2.0)]).unwrap();
+        let _: usize = conn.tvs_hset_vector(index_name, "k1", &v1).unwrap();
+        let _: usize = conn.tvs_hset_vector(index_name, "k2", &v2).unwrap();
+
+        let got: Vec<Vector16> = conn.tvs_get_vector(index_name, "k1").unwrap();
+        assert_eq!(got[0], v1);
+
+        let knn_results: Vec<(String, f32)> = conn.tvs_knnsearch(index_name, 1, &v2).unwrap();
+        assert_eq!(knn_results[0].0, "k2");
+
+        conn.tvs_del_index::<_, usize>(index_name).unwrap();
+    }
+}
@@ -0,0 +1,760 @@
+use crate::TairVectorError;
+use redis::{ErrorKind, FromRedisValue, RedisError, RedisResult, RedisWrite, ToRedisArgs, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Index algorithm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexType {
+    Flat,
+    Hnsw,
+}
+
+/// Distance metric of an index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DistanceType {
+    L2,
+    Ip,
+    Cosine,
+    Jaccard,
+}
+
+/// Element type of the vectors stored in an index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DataType {
+    #[default]
+    Float32,
+    Float16,
+    Binary,
+}
+
+/// How the `TEXT` field of an index is turned into a sparse vector.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LexicalAlgorithm {
+    /// The server tokenizes `TEXT` with the index analyzer and weighs terms
+    /// with BM25.
+    Bm25,
+    /// `TEXT` holds a client-computed `SparseVector`.
+    Vector,
+}
+
+impl IndexType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IndexType::Flat => "FLAT",
+            IndexType::Hnsw => "HNSW",
+        }
+    }
+}
+
+impl DistanceType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DistanceType::L2 => "L2",
+            DistanceType::Ip => "IP",
+            DistanceType::Cosine => "COSINE",
+            DistanceType::Jaccard => "JACCARD",
+        }
+    }
+}
+
+impl DataType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DataType::Float32 => "FLOAT32",
+            DataType::Float16 => "FLOAT16",
+            DataType::Binary => "BINARY",
+        }
+    }
+}
+
+impl LexicalAlgorithm {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LexicalAlgorithm::Bm25 => "bm25",
+            LexicalAlgorithm::Vector => "vector",
+        }
+    }
+}
+
+macro_rules! impl_str_enum {
+    ($($t:ty),*) => {
+        $(
+            impl fmt::Display for $t {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    f.write_str(self.as_str())
+                }
+            }
+
+            impl ToRedisArgs for $t {
+                fn write_redis_args<W>(&self, out: &mut W)
+                where
+                    W: ?Sized + RedisWrite,
+                {
+                    out.write_arg(self.as_str().as_bytes());
+                }
+            }
+        )*
+    };
+}
+
+impl_str_enum!(IndexType, DistanceType, DataType, LexicalAlgorithm);
+
+impl FromStr for IndexType {
+    type Err = RedisError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "FLAT" => Ok(IndexType::Flat),
+            "HNSW" => Ok(IndexType::Hnsw),
+            _ => Err(invalid_value("index type", s)),
+        }
+    }
+}
+
+impl FromStr for DistanceType {
+    type Err = RedisError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "L2" => Ok(DistanceType::L2),
+            "IP" => Ok(DistanceType::Ip),
+            "COSINE" => Ok(DistanceType::Cosine),
+            "JACCARD" => Ok(DistanceType::Jaccard),
+            _ => Err(invalid_value("distance type", s)),
+        }
+    }
+}
+
+impl FromStr for DataType {
+    type Err = RedisError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "FLOAT32" => Ok(DataType::Float32),
+            "FLOAT16" => Ok(DataType::Float16),
+            "BINARY" => Ok(DataType::Binary),
+            _ => Err(invalid_value("data type", s)),
+        }
+    }
+}
+
+impl FromStr for LexicalAlgorithm {
+    type Err = RedisError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "bm25" => Ok(LexicalAlgorithm::Bm25),
+            "vector" => Ok(LexicalAlgorithm::Vector),
+            _ => Err(invalid_value("lexical algorithm", s)),
+        }
+    }
+}
+
+fn invalid_value(what: &str, value: &str) -> RedisError {
+    RedisError::from((
+        ErrorKind::TypeError,
+        "Response was of incompatible type",
+        format!("invalid {}: {:?}", what, value),
+    ))
+}
+
+/// Everything needed to create an index with TVS.CREATEINDEX.
+///
+/// ```
+/// use tair_vector_rs::{DataType, DistanceType, IndexSpec, IndexType};
+///
+/// let spec = IndexSpec::new("my-index", 128, IndexType::Hnsw, DistanceType::Cosine)
+///     .data_type(DataType::Float16)
+///     .ef_construct(200)
+///     .m(24);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexSpec {
+    pub name: String,
+    pub dim: usize,
+    pub index_type: IndexType,
+    pub distance_type: DistanceType,
+    pub data_type: Option<DataType>,
+    pub ef_construct: Option<usize>,
+    pub m: Option<usize>,
+    pub auto_gc: Option<bool>,
+    pub lexical_algorithm: Option<LexicalAlgorithm>,
+    pub analyzer: Option<String>,
+    pub params: Vec<(String, String)>,
+}
+
+impl IndexSpec {
+    pub fn new(name: &str, dim: usize, index_type: IndexType, distance_type: DistanceType) -> Self {
+        IndexSpec {
+            name: name.to_owned(),
+            dim,
+            index_type,
+            distance_type,
+            data_type: None,
+            ef_construct: None,
+            m: None,
+            auto_gc: None,
+            lexical_algorithm: None,
+            analyzer: None,
+            params: vec![],
+        }
+    }
+
+    pub fn data_type(mut self, data_type: DataType) -> Self {
+        self.data_type = Some(data_type);
+        self
+    }
+
+    /// HNSW only.
+    pub fn ef_construct(mut self, ef_construct: usize) -> Self {
+        self.ef_construct = Some(ef_construct);
+        self
+    }
+
+    /// HNSW only.
+    pub fn m(mut self, m: usize) -> Self {
+        self.m = Some(m);
+        self
+    }
+
+    pub fn auto_gc(mut self, auto_gc: bool) -> Self {
+        self.auto_gc = Some(auto_gc);
+        self
+    }
+
+    /// Enables the `TEXT` field for hybrid search.
+    pub fn lexical_algorithm(mut self, lexical_algorithm: LexicalAlgorithm) -> Self {
+        self.lexical_algorithm = Some(lexical_algorithm);
+        self
+    }
+
+    /// Tokenizer for `TEXT` with `LexicalAlgorithm::Bm25`, e.g. `jieba`.
+    pub fn analyzer(mut self, analyzer: &str) -> Self {
+        self.analyzer = Some(analyzer.to_owned());
+        self
+    }
+
+    /// Any other index parameter, passed to the server as is.
+    pub fn param<K: ToString, V: ToString>(mut self, key: K, value: V) -> Self {
+        self.params.push((key.to_string(), value.to_string()));
+        self
+    }
+}
+
+impl ToRedisArgs for IndexSpec {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        self.name.write_redis_args(out);
+        self.dim.write_redis_args(out);
+        self.index_type.write_redis_args(out);
+        self.distance_type.write_redis_args(out);
+        if let Some(data_type) = self.data_type {
+            out.write_arg(b"data_type");
+            data_type.write_redis_args(out);
+        }
+        if let Some(ef_construct) = self.ef_construct {
+            out.write_arg(b"ef_construct");
+            ef_construct.write_redis_args(out);
+        }
+        if let Some(m) = self.m {
+            out.write_arg(b"M");
+            m.write_redis_args(out);
+        }
+        if let Some(auto_gc) = self.auto_gc {
+            out.write_arg(b"auto_gc");
+            out.write_arg(if auto_gc { b"true" } else { b"false" });
+        }
+        if let Some(lexical_algorithm) = self.lexical_algorithm {
+            out.write_arg(b"lexical_algorithm");
+            lexical_algorithm.write_redis_args(out);
+        }
+        if let Some(analyzer) = &self.analyzer {
+            out.write_arg(b"analyzer");
+            analyzer.write_redis_args(out);
+        }
+        self.params.write_redis_args(out);
+    }
+}
+
+/// Outcome of `tvs_ensure_index`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnsureIndex {
+    Created,
+    AlreadyMatches,
+}
+
+/// Names of the fields compared by `IndexSpec::diff`.
+pub(crate) const DIFF_FIELDS: [&str; 10] = [
+    "dimension",
+    "algorithm",
+    "distance_method",
+    "data_type",
+    "ef_construct",
+    "M",
+    "auto_gc",
+    "lexical_algorithm",
+    "analyzer",
+    "params",
+];
+
+/// A field of an existing index that differs from the requested `IndexSpec`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaDiff {
+    pub field: &'static str,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl fmt::Display for SchemaDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: expected {}, got {}",
+            self.field, self.expected, self.actual
+        )
+    }
+}
+
+impl IndexSpec {
+    /// Compares the spec with the metadata of an existing index.
+    ///
+    /// An unset data type is compared against the server default FLOAT32.
+    /// Other params are only compared when set in the spec, and differ if
+    /// the server does not report them.
+    pub fn diff(&self, info: &IndexInfo) -> Vec<SchemaDiff> {
+        fn push<T: PartialEq + fmt::Display>(
+            diffs: &mut Vec<SchemaDiff>,
+            field: &'static str,
+            expected: Option<T>,
+            actual: Option<T>,
+        ) {
+            let expected = match expected {
+                Some(expected) => expected,
+                None => return,
+            };
+            if actual.as_ref() != Some(&expected) {
+                diffs.push(SchemaDiff {
+                    field,
+                    expected: expected.to_string(),
+                    actual: actual.map_or_else(|| "unset".to_owned(), |a| a.to_string()),
+                });
+            }
+        }
+
+        let mut diffs = vec![];
+        push(
+            &mut diffs,
+            "dimension",
+            Some(self.dim),
+            Some(info.dimension),
+        );
+        push(
+            &mut diffs,
+            "algorithm",
+            Some(self.index_type),
+            Some(info.algorithm),
+        );
+        push(
+            &mut diffs,
+            "distance_method",
+            Some(self.distance_type),
+            Some(info.distance_method),
+        );
+        push(
+            &mut diffs,
+            "data_type",
+            Some(self.data_type.unwrap_or_default()),
+            Some(info.data_type),
+        );
+        push(
+            &mut diffs,
+            "ef_construct",
+            self.ef_construct,
+            info.ef_construct,
+        );
+        push(&mut diffs, "M", self.m, info.m);
+        push(&mut diffs, "auto_gc", self.auto_gc, info.auto_gc);
+        push(
+            &mut diffs,
+            "lexical_algorithm",
+            self.lexical_algorithm,
+            info.lexical_algorithm,
+        );
+        push(
+            &mut diffs,
+            "analyzer",
+            self.analyzer.as_ref(),
+            info.analyzer.as_ref(),
+        );
+        for (key, value) in &self.params {
+            let actual = info.param(key);
+            if !actual
+                .as_ref()
+                .is_some_and(|a| a.eq_ignore_ascii_case(value))
+            {
+                diffs.push(SchemaDiff {
+                    field: "params",
+                    expected: format!("{} {}", key, value),
+                    actual: format!("{} {}", key, actual.as_deref().unwrap_or("unset")),
+                });
+            }
+        }
+        diffs
+    }
+
+    /// `AlreadyMatches` if `info` matches the spec, a `SchemaMismatch` error
+    /// otherwise.
+    pub(crate) fn verify(&self, info: &IndexInfo) -> Result<EnsureIndex, TairVectorError> {
+        let diffs = self.diff(info);
+        if diffs.is_empty() {
+            Ok(EnsureIndex::AlreadyMatches)
+        } else {
+            Err(TairVectorError::SchemaMismatch(diffs))
+        }
+    }
+}
+
+/// Index metadata as returned by TVS.GETINDEX.
+///
+/// Tair replies with an empty array for a missing index, which fails to
+/// decode as `IndexInfo` or `Option<IndexInfo>`. Use `tvs_get_index_info`
+/// or `IndexInfo::from_reply` to get `None` instead. Keys this crate does
+/// not know about are kept in `extra`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexInfo {
+    pub dimension: usize,
+    pub algorithm: IndexType,
+    pub distance_method: DistanceType,
+    pub data_type: DataType,
+    pub data_count: u64,
+    pub current_record_count: u64,
+    pub delete_record_count: u64,
+    pub index_data_size: Option<u64>,
+    pub attribute_data_size: Option<u64>,
+    pub ef_construct: Option<usize>,
+    pub m: Option<usize>,
+    pub auto_gc: Option<bool>,
+    pub lexical_algorithm: Option<LexicalAlgorithm>,
+    pub analyzer: Option<String>,
+    pub extra: HashMap<String, String>,
+}
+
+impl IndexInfo {
+    /// The value the server reports for the index parameter `key`, as sent
+    /// with `IndexSpec::param`.
+    pub fn param(&self, key: &str) -> Option<String> {
+        match key.to_ascii_lowercase().as_str() {
+            "data_type" => Some(self.data_type.to_string()),
+            "ef_construct" => self.ef_construct.map(|v| v.to_string()),
+            "m" => self.m.map(|v| v.to_string()),
+            "auto_gc" => self.auto_gc.map(|v| v.to_string()),
+            "lexical_algorithm" => self.lexical_algorithm.map(|v| v.to_string()),
+            "analyzer" => self.analyzer.clone(),
+            _ => self
+                .extra
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                .map(|(_, v)| v.clone()),
+        }
+    }
+
+    /// Decodes a TVS.GETINDEX reply, with `None` for the nil or empty reply
+    /// of a missing index.
+    pub fn from_reply(v: &Value) -> RedisResult<Option<Self>> {
+        match v {
+            Value::Nil => Ok(None),
+            Value::Bulk(items) if items.is_empty() => Ok(None),
+            v => IndexInfo::from_redis_value(v).map(Some),
+        }
+    }
+}
+
+impl FromRedisValue for IndexInfo {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        let mut fields: HashMap<String, String> = HashMap::from_redis_value(v)?;
+
+        fn optional<T: FromStr>(
+            fields: &mut HashMap<String, String>,
+            key: &str,
+        ) -> RedisResult<Option<T>> {
+            fields
+                .remove(key)
+                .map(|v| v.parse::<T>().map_err(|_| invalid_value(key, &v)))
+                .transpose()
+        }
+
+        fn required<T: FromStr>(fields: &mut HashMap<String, String>, key: &str) -> RedisResult<T> {
+            optional(fields, key)?.ok_or_else(|| {
+                RedisError::from((
+                    ErrorKind::TypeError,
+                    "Response was of incompatible type",
+                    format!("missing {:?} in index info", key),
+                ))
+            })
+        }
+
+        Ok(IndexInfo {
+            dimension: required(&mut fields, "dimension")?,
+            algorithm: required(&mut fields, "algorithm")?,
+            distance_method: required(&mut fields, "distance_method")?,
+            data_type: optional(&mut fields, "data_type")?.unwrap_or_default(),
+            data_count: optional(&mut fields, "data_count")?.unwrap_or_default(),
+            current_record_count: optional(&mut fields, "current_record_count")?
+                .unwrap_or_default(),
+            delete_record_count: optional(&mut fields, "delete_record_count")?.unwrap_or_default(),
+            index_data_size: optional(&mut fields, "index_data_size")?,
+            attribute_data_size: optional(&mut fields, "attribute_data_size")?,
+            ef_construct: optional(&mut fields, "ef_construct")?,
+            m: optional(&mut fields, "M")?,
+            auto_gc: optional(&mut fields, "auto_gc")?,
+            lexical_algorithm: optional(&mut fields, "lexical_algorithm")?,
+            analyzer: fields.remove("analyzer"),
+            extra: fields,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TairVectorCommands;
+
+    fn args(spec: &IndexSpec) -> Vec<String> {
+        spec.to_redis_args()
+            .into_iter()
+            .map(|a| String::from_utf8(a).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn spec_args() {
+        let spec = IndexSpec::new("idx", 8, IndexType::Flat, DistanceType::Jaccard);
+        assert_eq!(args(&spec), ["idx", "8", "FLAT", "JACCARD"]);
+
+        let spec = IndexSpec::new("idx", 128, IndexType::Hnsw, DistanceType::Ip)
+            .data_type(DataType::Float16)
+            .ef_construct(200)
+            .m(24)
+            .auto_gc(true)
+            .lexical_algorithm(LexicalAlgorithm::Bm25)
+            .analyzer("jieba")
+            .param("max_elements", 1000);
+        assert_eq!(
+            args(&spec),
+            [
+                "idx",
+                "128",
+                "HNSW",
+                "IP",
+                "data_type",
+                "FLOAT16",
+                "ef_construct",
+                "200",
+                "M",
+                "24",
+                "auto_gc",
+                "true",
+                "lexical_algorithm",
+                "bm25",
+                "analyzer",
+                "jieba",
+                "max_elements",
+                "1000"
+            ]
+        );
+    }
+
+    #[test]
+    fn index_info() {
+        let data = |s: &str| Value::Data(s.as_bytes().to_vec());
+        let reply = Value::Bulk(vec![
+            data("dimension"),
+            data("128"),
+            data("algorithm"),
+            data("HNSW"),
+            data("distance_method"),
+            data("IP"),
+            data("data_count"),
+            data("10"),
+            data("current_record_count"),
+            data("12"),
+            data("delete_record_count"),
+            data("2"),
+            data("M"),
+            data("16"),
+            data("ef_construct"),
+            data("100"),
+            data("auto_gc"),
+            data("false"),
+            data("index_data_size"),
+            data("4096"),
+            data("lexical_algorithm"),
+            data("vector"),
+            data("some_new_key"),
+            data("some_value"),
+        ]);
+        let info = IndexInfo::from_redis_value(&reply).unwrap();
+        assert_eq!(info.dimension, 128);
+        assert_eq!(info.algorithm, IndexType::Hnsw);
+        assert_eq!(info.distance_method, DistanceType::Ip);
+        assert_eq!(info.data_type, DataType::Float32);
+        assert_eq!(info.data_count, 10);
+        assert_eq!(info.current_record_count, 12);
+        assert_eq!(info.delete_record_count, 2);
+        assert_eq!(info.m, Some(16));
+        assert_eq!(info.ef_construct, Some(100));
+        assert_eq!(info.auto_gc, Some(false));
+        assert_eq!(info.index_data_size, Some(4096));
+        assert_eq!(info.attribute_data_size, None);
+        assert_eq!(info.lexical_algorithm, Some(LexicalAlgorithm::Vector));
+        assert_eq!(info.analyzer, None);
+        assert_eq!(info.extra.len(), 1);
+        assert_eq!(info.extra.get("some_new_key").unwrap(), "some_value");
+
+        let missing: Option<IndexInfo> = redis::from_redis_value(&Value::Nil).unwrap();
+        assert!(missing.is_none());
+        assert_eq!(IndexInfo::from_reply(&Value::Nil).unwrap(), None);
+        assert_eq!(IndexInfo::from_reply(&Value::Bulk(vec![])).unwrap(), None);
+        assert_eq!(IndexInfo::from_reply(&reply).unwrap(), Some(info));
+
+        let reply = Value::Bulk(vec![data("dimension"), data("128")]);
+        assert!(IndexInfo::from_redis_value(&reply).is_err());
+    }
+
+    fn info(spec: &IndexSpec) -> IndexInfo {
+        IndexInfo {
+            dimension: spec.dim,
+            algorithm: spec.index_type,
+            distance_method: spec.distance_type,
+            data_type: DataType::Float32,
+            data_count: 0,
+            current_record_count: 0,
+            delete_record_count: 0,
+            index_data_size: None,
+            attribute_data_size: None,
+            ef_construct: Some(100),
+            m: Some(16),
+            auto_gc: None,
+            lexical_algorithm: None,
+            analyzer: None,
+            extra: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn spec_diff() {
+        let spec = IndexSpec::new("idx", 128, IndexType::Hnsw, DistanceType::L2);
+        assert!(spec.diff(&info(&spec)).is_empty());
+        assert_eq!(
+            spec.verify(&info(&spec)).unwrap(),
+            EnsureIndex::AlreadyMatches
+        );
+
+        let other = spec
+            .clone()
+            .data_type(DataType::Float16)
+            .ef_construct(200)
+            .m(16)
+            .auto_gc(true)
+            .param("max_elements", 1000)
+            .param("initial_cap", 10);
+        let mut existing = info(&spec);
+        existing.dimension = 64;
+        existing.distance_method = DistanceType::Ip;
+        existing
+            .extra
+            .insert("MAX_ELEMENTS".to_owned(), "1000".to_owned());
+        let diffs = other.diff(&existing);
+        let fields: Vec<&str> = diffs.iter().map(|d| d.field).collect();
+        assert_eq!(
+            fields,
+            [
+                "dimension",
+                "distance_method",
+                "data_type",
+                "ef_construct",
+                "auto_gc",
+                "params"
+            ]
+        );
+        assert_eq!(diffs[0].to_string(), "dimension: expected 128, got 64");
+        assert_eq!(diffs[4].to_string(), "auto_gc: expected true, got unset");
+        assert_eq!(
+            diffs[5].to_string(),
+            "params: expected initial_cap 10, got initial_cap unset"
+        );
+        match other.verify(&existing) {
+            Err(TairVectorError::SchemaMismatch(d)) => assert_eq!(d, diffs),
+            r => panic!("unexpected {:?}", r),
+        }
+    }
+
+    #[test]
+    fn ensure_index() {
+        let redis_url = crate::mock::test_url();
+
+        let index_name = "test-ensure-index";
+        let client = redis::Client::open(redis_url).unwrap();
+        let mut conn = client.get_connection().unwrap();
+
+        // cleanup
+        conn.tvs_del_index::<_, usize>(index_name).unwrap();
+
+        assert_eq!(conn.tvs_get_index_info(index_name).unwrap(), None);
+        let spec = IndexSpec::new(index_name, 16, IndexType::Hnsw, DistanceType::L2).m(24);
+        assert_eq!(conn.tvs_ensure_index(&spec).unwrap(), EnsureIndex::Created);
+        assert_eq!(
+            conn.tvs_ensure_index(&spec).unwrap(),
+            EnsureIndex::AlreadyMatches
+        );
+
+        let other = IndexSpec::new(index_name, 32, IndexType::Hnsw, DistanceType::Ip).m(24);
+        match conn.tvs_ensure_index(&other) {
+            Err(TairVectorError::SchemaMismatch(diffs)) => {
+                let fields: Vec<&str> = diffs.iter().map(|d| d.field).collect();
+                assert_eq!(fields, ["dimension", "distance_method"]);
+            }
+            r => panic!("unexpected {:?}", r),
+        }
+        match conn.tvs_ensure_index(&spec.clone().m(32).ef_construct(200)) {
+            Err(TairVectorError::SchemaMismatch(diffs)) => {
+                let fields: Vec<&str> = diffs.iter().map(|d| d.field).collect();
+                assert_eq!(fields, ["ef_construct", "M"]);
+            }
+            r => panic!("unexpected {:?}", r),
+        }
+
+        conn.tvs_del_index::<_, usize>(index_name).unwrap();
+    }
+
+    #[test]
+    fn create_index_spec() {
+        let redis_url = crate::mock::test_url();
+
+        let index_name = "test-create-index-spec";
+        let client = redis::Client::open(redis_url).unwrap();
+        let mut conn = client.get_connection().unwrap();
+
+        // cleanup
+        conn.tvs_del_index::<_, usize>(index_name).unwrap();
+
+        let spec = IndexSpec::new(index_name, 16, IndexType::Hnsw, DistanceType::Cosine)
+            .ef_construct(100)
+            .m(24);
+        let created: bool = conn.tvs_create_index_spec(&spec).unwrap();
+        assert!(created);
+
+        let index_info: IndexInfo = conn.tvs_get_index(index_name).unwrap();
+        assert_eq!(index_info.dimension, 16);
+        assert_eq!(index_info.algorithm, IndexType::Hnsw);
+        assert_eq!(index_info.distance_method, DistanceType::Cosine);
+        assert_eq!(index_info.ef_construct, Some(100));
+        assert_eq!(index_info.m, Some(24));
+
+        conn.tvs_del_index::<_, usize>(index_name).unwrap();
+    }
+}
@@ -6,17 +6,55 @@ use redis::{
     ConnectionLike, ErrorKind, FromRedisValue, Iter, RedisError, RedisResult, RedisWrite,
     ToRedisArgs, Value,
 };
+use std::borrow::Cow;
 use std::fmt;
+use std::ops::Deref;
 use std::str;
 
 #[macro_use]
 pub mod macros;
 
+mod binary;
 #[cfg(feature = "bulk")]
 mod bulk;
 
+#[cfg(feature = "cluster")]
+mod cluster;
+mod error;
+mod filter;
+#[cfg(feature = "half")]
+mod float16;
+
+mod index;
+#[cfg(any(test, feature = "mock"))]
+mod mock;
+mod search;
+mod sparse;
+mod validated;
+
+pub use crate::binary::BinaryVector;
 #[cfg(feature = "bulk")]
-pub use crate::bulk::BulkOps;
+pub use crate::bulk::{BulkEntries, BulkErrorPolicy, BulkLoadOptions, BulkLoadReport, BulkOps};
+#[cfg(feature = "cluster")]
+pub use crate::cluster::{ClusterScanIndex, TairVectorClusterCommands};
+#[cfg(feature = "cluster-async")]
+pub use crate::cluster::{ClusterScanIndexAsync, TairVectorClusterAsyncCommands};
+pub use crate::error::TairVectorError;
+#[cfg(feature = "aio")]
+pub use crate::error::TairVectorFuture;
+use crate::filter::check_filter;
+pub use crate::filter::{field, AsFilter, CompareOp, Field, Filter, FilterValue};
+#[cfg(feature = "half")]
+pub use crate::float16::Vector16;
+pub use crate::index::{
+    DataType, DistanceType, EnsureIndex, IndexInfo, IndexSpec, IndexType, LexicalAlgorithm,
+    SchemaDiff,
+};
+#[cfg(feature = "mock")]
+pub use crate::mock::{InMemoryTairVector, MockServer};
+pub use crate::search::{KnnQuery, KnnResult, SearchHit};
+pub use crate::sparse::SparseVector;
+pub use crate::validated::ValidatedIndex;
 
 implement_commands! {
     'a
@@ -55,6 +93,11 @@ implement_commands! {
             .arg(params)
     }
 
+    /// TVS.CREATEINDEX with the name, dimension, algorithm, metric and params of an `IndexSpec`
+    fn tvs_create_index_spec<>(spec: &'a IndexSpec) {
+        redis::cmd("TVS.CREATEINDEX").arg(spec)
+    }
+
     /// TVS.GETINDEX index_name
     fn tvs_get_index<K: ToRedisArgs>(
         index_name: K
@@ -68,16 +111,16 @@ implement_commands! {
     }
 
     /// TVS.HSET index_name key VECTOR vector
-    fn tvs_hset_vector<IK: ToRedisArgs, VK: ToRedisArgs, V: ToRedisArgs>(
+    fn tvs_hset_vector<IK: ToRedisArgs, VK: ToRedisArgs, V: AsVector>(
         index_name: IK,
         key: VK,
         vector: V
-    )  {
+    ) check(vector.check()) {
         redis::cmd("TVS.HSET")
             .arg(index_name)
             .arg(key)
             .arg("VECTOR")
-            .arg(vector)
+            .arg(VectorArg(vector))
     }
 
     /// TVS.HSET index_name key field value
@@ -106,6 +149,34 @@ implement_commands! {
             .arg(attrs)
     }
 
+    /// TVS.HSET index_name key VECTOR vector TEXT text [field1 val1]...
+    ///
+    /// `text` is a `SparseVector` with `LexicalAlgorithm::Vector`, or a
+    /// string with `LexicalAlgorithm::Bm25`.
+    fn tvs_hset_hybrid<
+        IK: ToRedisArgs,
+        VK: ToRedisArgs,
+        V: AsVector,
+        S: ToRedisArgs,
+        FK: ToRedisArgs,
+        FV: ToRedisArgs
+    >(
+        index_name: IK,
+        key: VK,
+        vector: V,
+        text: S,
+        attrs: &'a [(FK, FV)]
+    ) check(vector.check()) {
+        redis::cmd("TVS.HSET")
+            .arg(index_name)
+            .arg(key)
+            .arg("VECTOR")
+            .arg(VectorArg(vector))
+            .arg("TEXT")
+            .arg(text)
+            .arg(attrs)
+    }
+
     /// TVS.HGETALL index_name key
     fn tvs_hgetall<IK: ToRedisArgs, VK: ToRedisArgs>(
         index_name: IK,
@@ -139,52 +210,620 @@ implement_commands! {
             .arg(attrs)
     }
 
+    /// TVS.DEL index_name key1...
+    fn tvs_del<IK: ToRedisArgs, VK: ToRedisArgs>(index_name: IK, keys: VK) {
+        redis::cmd("TVS.DEL").arg(index_name).arg(keys)
+    }
+
+    /// TVS.HDEL index_name key field1...
+    fn tvs_hdel<IK: ToRedisArgs, VK: ToRedisArgs, F: ToRedisArgs>(
+        index_name: IK,
+        key: VK,
+        fields: F
+    ) {
+        redis::cmd("TVS.HDEL")
+            .arg(index_name)
+            .arg(key)
+            .arg(fields)
+    }
+
     /// TVS.KNNSEARCH index_name topk vector
-    fn tvs_knnsearch<K: ToRedisArgs, V: ToRedisArgs>(index_name: K, topk: usize, vector: V) {
+    fn tvs_knnsearch<K: ToRedisArgs, V: AsVector>(
+        index_name: K,
+        topk: usize,
+        vector: V
+    ) check(vector.check()) {
         redis::cmd("TVS.KNNSEARCH")
             .arg(index_name)
             .arg(topk)
-            .arg(vector)
+            .arg(VectorArg(vector))
     }
 
     /// TVS.KNNSEARCH index_name topk vector ef_search 200
-    fn tvs_knnsearch_with_params<K: ToRedisArgs, V: ToRedisArgs, PK: ToRedisArgs, PV: ToRedisArgs>(
+    fn tvs_knnsearch_with_params<K: ToRedisArgs, V: AsVector, PK: ToRedisArgs, PV: ToRedisArgs>(
         index_name: K,
         topk: usize,
         vector: V,
         params: &'a [(PK, PV)]
-    ) {
+    ) check(vector.check()) {
+        redis::cmd("TVS.KNNSEARCH")
+            .arg(index_name)
+            .arg(topk)
+            .arg(VectorArg(vector))
+            .arg(params)
+    }
+
+    /// TVS.KNNSEARCH index_name topk vector [filter] [ef_search 200] [MAX_DIST d], or
+    /// TVS.KNNSEARCHFIELD index_name topk vector field_count field1... [filter] [params]
+    /// if the query has output fields
+    fn tvs_knnsearch_query<K: ToRedisArgs, V: AsVector>(
+        index_name: K,
+        vector: V,
+        query: &'a KnnQuery
+    ) check(vector.check(), query.check()) {
+        redis::cmd(query.command_name())
+            .arg(index_name)
+            .arg(query.topk)
+            .arg(VectorArg(vector))
+            .arg(query.tail_args())
+    }
+
+    /// TVS.KNNSEARCH index_name topk vector TEXT sparse_vector hybrid_ratio ratio [ef_search 200]
+    fn tvs_hybrid_knnsearch<
+        K: ToRedisArgs,
+        V: AsVector,
+        S: ToRedisArgs,
+        PK: ToRedisArgs,
+        PV: ToRedisArgs
+    >(
+        index_name: K,
+        topk: usize,
+        vector: V,
+        sparse_vector: S,
+        hybrid_ratio: f32,
+        params: &'a [(PK, PV)]
+    ) check(vector.check()) {
         redis::cmd("TVS.KNNSEARCH")
             .arg(index_name)
             .arg(topk)
-            .arg(vector)
+            .arg(VectorArg(vector))
+            .arg("TEXT")
+            .arg(sparse_vector)
+            .arg("hybrid_ratio")
+            .arg(hybrid_ratio)
+            .arg(params)
+    }
+
+    /// TVS.KNNSEARCH index_name topk vector TEXT query hybrid_ratio ratio [ef_search 200]
+    ///
+    /// Full-text search on indices with `LexicalAlgorithm::Bm25`, combined
+    /// with the vector search by `hybrid_ratio`.
+    fn tvs_text_knnsearch<K: ToRedisArgs, V: AsVector, PK: ToRedisArgs, PV: ToRedisArgs>(
+        index_name: K,
+        topk: usize,
+        vector: V,
+        query: &'a str,
+        hybrid_ratio: f32,
+        params: &'a [(PK, PV)]
+    ) check(vector.check()) {
+        redis::cmd("TVS.KNNSEARCH")
+            .arg(index_name)
+            .arg(topk)
+            .arg(VectorArg(vector))
+            .arg("TEXT")
+            .arg(query)
+            .arg("hybrid_ratio")
+            .arg(hybrid_ratio)
+            .arg(params)
+    }
+
+    /// TVS.GETDISTANCE index_name vector key_count key1... [TOPN topn] [MAX_DIST max_dist] [FILTER filter]
+    fn tvs_get_distance<K: ToRedisArgs, V: AsVector, VK: ToRedisArgs, F: AsFilter>(
+        index_name: K,
+        vector: V,
+        keys: &'a [VK],
+        topn: Option<usize>,
+        max_dist: Option<f32>,
+        filter: Option<F>
+    ) check(vector.check(), check_filter(&filter)) {
+        redis::cmd("TVS.GETDISTANCE")
+            .arg(index_name)
+            .arg(VectorArg(vector))
+            .arg(keys.len())
+            .arg(keys)
+            .arg(topn.map(|n| ("TOPN", n)))
+            .arg(max_dist.map(|d| ("MAX_DIST", d)))
+            .arg(filter.map(|f| ("FILTER", f)))
+    }
+
+    /// TVS.MKNNSEARCH index_name topk vector_count vector1...
+    fn tvs_mknnsearch<K: ToRedisArgs, V: AsVector>(
+        index_name: K,
+        topk: usize,
+        vectors: &'a [V]
+    ) check(check_vectors(vectors)) {
+        redis::cmd("TVS.MKNNSEARCH")
+            .arg(index_name)
+            .arg(topk)
+            .arg(vectors.len())
+            .arg(VectorArgs(vectors))
+    }
+
+    /// TVS.MKNNSEARCH index_name topk vector_count vector1..., with one
+    /// vector per row of `vectors`
+    #[cfg(feature = "ndarray")]
+    fn tvs_mknnsearch_ndarray<K: ToRedisArgs, T: VectorElement>(
+        index_name: K,
+        topk: usize,
+        vectors: ArrayView2<'a, T>
+    ) check(check_elements(vectors.iter())) {
+        redis::cmd("TVS.MKNNSEARCH")
+            .arg(index_name)
+            .arg(topk)
+            .arg(vectors.nrows())
+            .arg(VectorRows(vectors))
+    }
+
+    /// TVS.MKNNSEARCH index_name topk vector_count vector1... [filter] [ef_search 200]
+    fn tvs_mknnsearch_with_params<
+        K: ToRedisArgs,
+        V: AsVector,
+        F: AsFilter,
+        PK: ToRedisArgs,
+        PV: ToRedisArgs
+    >(
+        index_name: K,
+        topk: usize,
+        vectors: &'a [V],
+        filter: Option<F>,
+        params: &'a [(PK, PV)]
+    ) check(check_vectors(vectors), check_filter(&filter)) {
+        redis::cmd("TVS.MKNNSEARCH")
+            .arg(index_name)
+            .arg(topk)
+            .arg(vectors.len())
+            .arg(VectorArgs(vectors))
+            .arg(filter)
+            .arg(params)
+    }
+
+    /// TVS.MINDEXKNNSEARCH index_count index_name1... topk vector [ef_search 200]
+    fn tvs_mindex_knnsearch<
+        K: ToRedisArgs,
+        V: AsVector,
+        PK: ToRedisArgs,
+        PV: ToRedisArgs
+    >(
+        index_names: &'a [K],
+        topk: usize,
+        vector: V,
+        params: &'a [(PK, PV)]
+    ) check(vector.check(), check_single_node::<Self>(MINDEX_ON_CLUSTER)) {
+        redis::cmd("TVS.MINDEXKNNSEARCH")
+            .arg(index_names.len())
+            .arg(index_names)
+            .arg(topk)
+            .arg(VectorArg(vector))
+            .arg(params)
+    }
+
+    /// TVS.MINDEXMKNNSEARCH index_count index_name1... topk vector_count vector1... [ef_search 200]
+    fn tvs_mindex_mknnsearch<
+        K: ToRedisArgs,
+        V: AsVector,
+        PK: ToRedisArgs,
+        PV: ToRedisArgs
+    >(
+        index_names: &'a [K],
+        topk: usize,
+        vectors: &'a [V],
+        params: &'a [(PK, PV)]
+    ) check(check_vectors(vectors), check_single_node::<Self>(MINDEX_ON_CLUSTER)) {
+        redis::cmd("TVS.MINDEXMKNNSEARCH")
+            .arg(index_names.len())
+            .arg(index_names)
+            .arg(topk)
+            .arg(vectors.len())
+            .arg(VectorArgs(vectors))
             .arg(params)
     }
 }
 
 impl<T> TairVectorCommands for T where T: ConnectionLike {}
+
+const MINDEX_ON_CLUSTER: &str =
+    "multi-index searches are routed by their index count, not by the index names";
+const SCAN_INDEX_ON_CLUSTER: &str =
+    "TVS.SCANINDEX only reaches one shard, use tvs_cluster_scan_index";
+impl TairVectorPipeline for redis::Pipeline {
+    #[inline]
+    fn add_tvs_command(&mut self, cmd: redis::Cmd) -> &mut Self {
+        self.add_command(cmd)
+    }
+}
+#[cfg(feature = "cluster")]
+impl TairVectorPipeline for redis::cluster::ClusterPipeline {
+    #[inline]
+    fn add_tvs_command(&mut self, cmd: redis::Cmd) -> &mut Self {
+        let name = match cmd.args_iter().next() {
+            Some(redis::Arg::Simple(name)) => name,
+            _ => b"",
+        };
+        if let b"TVS.MINDEXKNNSEARCH" | b"TVS.MINDEXMKNNSEARCH" = name {
+            // routed by the index count, queue a command the server rejects
+            // instead, as for invalid vectors
+            let mut rejected = redis::cmd(str::from_utf8(name).unwrap_or_default());
+            rejected.arg(MINDEX_ON_CLUSTER);
+            return self.add_command(rejected);
+        }
+        self.add_command(cmd)
+    }
+}
 #[cfg(feature = "aio")]
 impl<T> TairVectorAsyncCommands for T where T: redis::aio::ConnectionLike + Send + Sized {}
 
+/// A dense f32 vector.
+#[derive(Debug, Clone, PartialEq)]
 pub struct Vector(Vec<f32>);
 
-trait VectorToRedisArgs: ToRedisArgs {}
+#[cfg(feature = "ndarray")]
+pub struct NdArrayVector<'a, T: VectorElement = f32>(ArrayView1<'a, T>);
+
+/// Element types that can be sent to and read back from TairVector.
+pub trait VectorElement: Copy + str::FromStr {
+    /// The index data type holding this element type.
+    const DATA_TYPE: DataType;
+
+    /// Lossless conversion, used for formatting.
+    fn to_f32(self) -> f32;
+
+    /// Why an index of `DATA_TYPE` cannot store `self`, if it cannot.
+    fn invalid(self) -> Option<&'static str> {
+        (!self.to_f32().is_finite()).then_some("not finite")
+    }
+}
+
+impl VectorElement for f32 {
+    const DATA_TYPE: DataType = DataType::Float32;
+
+    #[inline]
+    fn to_f32(self) -> f32 {
+        self
+    }
+}
+
+/// Rejects elements TairVector cannot store, such as NaN and infinity.
+fn check_elements<'a, T: VectorElement + 'a, I: IntoIterator<Item = &'a T>>(
+    values: I,
+) -> RedisResult<()> {
+    match values
+        .into_iter()
+        .enumerate()
+        .find_map(|(i, &x)| x.invalid().map(|reason| (i, reason, x.to_f32())))
+    {
+        Some((i, reason, x)) => Err(invalid_argument(
+            "Invalid vector",
+            format!("element {} is {}: {}", i, reason, x),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// An argument rejected before sending the command. `TairVectorError::from`
+/// classifies it as `InvalidParameter`.
+fn invalid_argument(description: &'static str, detail: String) -> RedisError {
+    RedisError::from((ErrorKind::ClientError, description, detail))
+}
+
+impl Vector {
+    pub fn new(values: Vec<f32>) -> RedisResult<Self> {
+        check_elements(values.iter())?;
+        Ok(Vector(values))
+    }
+
+    pub fn into_inner(self) -> Vec<f32> {
+        self.0
+    }
+}
 
-impl VectorToRedisArgs for Vec<f32> {}
+impl TryFrom<Vec<f32>> for Vector {
+    type Error = RedisError;
+
+    /// Same as `Vector::new`.
+    fn try_from(values: Vec<f32>) -> Result<Self, Self::Error> {
+        Vector::new(values)
+    }
+}
+
+impl Deref for Vector {
+    type Target = [f32];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
 
 #[cfg(feature = "ndarray")]
-pub struct NdArrayVector<'a>(ArrayView1<'a, f32>);
+impl<'a, T: VectorElement> NdArrayVector<'a, T> {
+    pub fn new(values: ArrayView1<'a, T>) -> RedisResult<Self> {
+        check_elements(values.iter())?;
+        Ok(NdArrayVector(values))
+    }
+}
 
-impl fmt::Display for Vector {
+/// Formats `[x1,x2,...]` with the shortest decimal that parses back to the
+/// exact same f32, writing straight into the output.
+struct VectorFormat<I>(I);
+
+impl<'a, T: VectorElement + 'a, I: Iterator<Item = &'a T> + Clone> fmt::Display
+    for VectorFormat<I>
+{
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "[")?;
-        for (i, value) in self.0.iter().enumerate() {
+        let mut buffer = ryu::Buffer::new();
+        f.write_str("[")?;
+        for (i, &value) in self.0.clone().enumerate() {
             if i > 0 {
-                write!(f, ",")?;
+                f.write_str(",")?;
             }
-            write!(f, "{}", value)?;
+            let s = buffer.format(value.to_f32());
+            f.write_str(s.strip_suffix(".0").unwrap_or(s))?;
         }
-        write!(f, "]")
+        f.write_str("]")
+    }
+}
+
+/// Anything that can be passed as the vector argument of a command.
+///
+/// Commands call `check` before sending, so a NaN or infinite element fails
+/// the command with an `InvalidParameter` error instead of reaching the
+/// server. Pipelines cannot fail while queuing a command: the vector is
+/// replaced by an argument the server rejects, and the error shows up in
+/// the reply of that command.
+pub trait AsVector {
+    /// Writes the vector in the `[x1,x2,...]` format used by TairVector.
+    fn fmt_vector(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result;
+
+    /// Rejects elements TairVector cannot store.
+    fn check(&self) -> RedisResult<()>;
+
+    /// Number of elements.
+    fn dim(&self) -> usize;
+
+    /// The index data type this vector is meant for.
+    fn data_type(&self) -> DataType;
+}
+
+impl<V: AsVector + ?Sized> AsVector for &V {
+    fn fmt_vector(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt_vector(f)
+    }
+
+    fn check(&self) -> RedisResult<()> {
+        (**self).check()
+    }
+
+    fn dim(&self) -> usize {
+        (**self).dim()
+    }
+
+    fn data_type(&self) -> DataType {
+        (**self).data_type()
+    }
+}
+
+impl<T: VectorElement> AsVector for [T] {
+    fn fmt_vector(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&VectorFormat(self.iter()), f)
+    }
+
+    fn check(&self) -> RedisResult<()> {
+        check_elements(self.iter())
+    }
+
+    fn dim(&self) -> usize {
+        self.len()
+    }
+
+    fn data_type(&self) -> DataType {
+        T::DATA_TYPE
+    }
+}
+
+/// Implements `AsVector` for containers that deref to `[T]`.
+macro_rules! impl_as_vector_via_slice {
+    ($([$($gen:tt)*] $t:ty;)*) => {
+        $(
+            impl<$($gen)*> AsVector for $t {
+                fn fmt_vector(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    self[..].fmt_vector(f)
+                }
+
+                fn check(&self) -> RedisResult<()> {
+                    self[..].check()
+                }
+
+                fn dim(&self) -> usize {
+                    self.len()
+                }
+
+                fn data_type(&self) -> DataType {
+                    self[..].data_type()
+                }
+            }
+        )*
+    };
+}
+
+impl_as_vector_via_slice! {
+    [T: VectorElement, const N: usize] [T; N];
+    [T: VectorElement] Vec<T>;
+    [T: VectorElement] Cow<'_, [T]>;
+    [] Vector;
+}
+
+#[cfg(feature = "ndarray")]
+impl<T: VectorElement> AsVector for ArrayView1<'_, T> {
+    fn fmt_vector(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&VectorFormat(self.iter()), f)
+    }
+
+    fn check(&self) -> RedisResult<()> {
+        check_elements(self.iter())
+    }
+
+    fn dim(&self) -> usize {
+        self.len()
+    }
+
+    fn data_type(&self) -> DataType {
+        T::DATA_TYPE
+    }
+}
+
+#[cfg(feature = "ndarray")]
+impl<T: VectorElement> AsVector for Array1<T> {
+    fn fmt_vector(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.view().fmt_vector(f)
+    }
+
+    fn check(&self) -> RedisResult<()> {
+        check_elements(self.iter())
+    }
+
+    fn dim(&self) -> usize {
+        self.len()
+    }
+
+    fn data_type(&self) -> DataType {
+        T::DATA_TYPE
+    }
+}
+
+#[cfg(feature = "ndarray")]
+impl<T: VectorElement> AsVector for NdArrayVector<'_, T> {
+    fn fmt_vector(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt_vector(f)
+    }
+
+    fn check(&self) -> RedisResult<()> {
+        // checked by `NdArrayVector::new`
+        Ok(())
+    }
+
+    fn dim(&self) -> usize {
+        self.0.len()
+    }
+
+    fn data_type(&self) -> DataType {
+        T::DATA_TYPE
+    }
+}
+
+/// Sends an `AsVector` as a single command argument.
+struct VectorArg<V>(V);
+
+impl<V: AsVector> fmt::Display for VectorArg<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt_vector(f)
+    }
+}
+
+impl<V: AsVector> ToRedisArgs for VectorArg<V> {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        match self.0.check() {
+            Ok(()) => out.write_arg_fmt(self),
+            // only reached from pipelines, which cannot report the error
+            // themselves: make the server reject the command instead
+            Err(e) => out.write_arg_fmt(format_args!(
+                "invalid vector: {}",
+                e.detail().unwrap_or_default()
+            )),
+        }
+    }
+}
+
+/// Sends each vector of a batch as its own argument.
+struct VectorArgs<'a, V>(&'a [V]);
+
+impl<V: AsVector> ToRedisArgs for VectorArgs<'_, V> {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        for vector in self.0 {
+            VectorArg(vector).write_redis_args(out);
+        }
+    }
+
+    fn is_single_arg(&self) -> bool {
+        self.0.len() == 1
+    }
+}
+
+/// Sends each row of an array as its own vector argument.
+#[cfg(feature = "ndarray")]
+struct VectorRows<'a, T>(ArrayView2<'a, T>);
+
+#[cfg(feature = "ndarray")]
+impl<T: VectorElement> ToRedisArgs for VectorRows<'_, T> {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        for row in self.0.outer_iter() {
+            VectorArg(row).write_redis_args(out);
+        }
+    }
+
+    fn is_single_arg(&self) -> bool {
+        self.0.nrows() == 1
+    }
+}
+
+/// Rejects a batch of vectors with non-finite elements or differing
+/// dimensions.
+fn check_vectors<V: AsVector>(vectors: &[V]) -> RedisResult<()> {
+    for (i, vector) in vectors.iter().enumerate() {
+        vector.check()?;
+        if vector.dim() != vectors[0].dim() {
+            return Err(invalid_argument(
+                "Invalid vector",
+                format!(
+                    "vector {} has {} elements, vector 0 has {}",
+                    i,
+                    vector.dim(),
+                    vectors[0].dim()
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Rejects commands that a cluster connection cannot route to the right
+/// shard, before they are sent.
+#[cfg_attr(
+    not(feature = "cluster"),
+    allow(unused_variables, clippy::extra_unused_type_parameters)
+)]
+fn check_single_node<C: ?Sized>(detail: &str) -> RedisResult<()> {
+    #[cfg(feature = "cluster")]
+    if cluster::is_cluster_connection::<C>() {
+        return Err(RedisError::from((
+            ErrorKind::ClientError,
+            "Not supported on cluster connections",
+            detail.to_owned(),
+        )));
+    }
+    Ok(())
+}
+
+impl fmt::Display for Vector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        VectorFormat(self.0.iter()).fmt(f)
     }
 }
 
@@ -193,55 +832,92 @@ impl ToRedisArgs for Vector {
     where
         W: ?Sized + RedisWrite,
     {
-        let encoded = format!(
-            "[{}]",
-            self.0
-                .iter()
-                .map(|x| x.to_string())
-                .collect::<Vec<String>>()
-                .join(",")
-        );
-        out.write_arg_fmt(encoded);
+        VectorArg(self).write_redis_args(out);
     }
 }
 
 #[cfg(feature = "ndarray")]
-impl ToRedisArgs for NdArrayVector<'_> {
+impl<T: VectorElement> ToRedisArgs for NdArrayVector<'_, T> {
     fn write_redis_args<W>(&self, out: &mut W)
     where
         W: ?Sized + RedisWrite,
     {
-        let encoded = format!(
-            "[{}]",
-            self.0
-                .iter()
-                .map(|x| x.to_string())
-                .collect::<Vec<String>>()
-                .join(",")
-        );
-        out.write_arg_fmt(encoded);
+        out.write_arg_fmt(VectorFormat(self.0.iter()));
+    }
+}
+
+fn vector_parse_error(detail: String) -> RedisError {
+    RedisError::from((ErrorKind::TypeError, "Invalid vector", detail))
+}
+
+impl TryFrom<&[u8]> for Vector {
+    type Error = RedisError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let s = str::from_utf8(data).map_err(|e| {
+            vector_parse_error(format!("invalid utf-8 at byte {}", e.valid_up_to()))
+        })?;
+        s.parse()
+    }
+}
+
+/// Parses the `[x1,x2,...]` format used by TairVector.
+fn parse_elements<T: VectorElement>(s: &str) -> RedisResult<Vec<T>>
+where
+    T::Err: fmt::Display,
+{
+    let trimmed = s.trim();
+    let inner = trimmed
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .ok_or_else(|| vector_parse_error(format!("expected [x1,x2,...], got {:?}", s)))?;
+    if inner.trim().is_empty() {
+        return Ok(vec![]);
+    }
+
+    // byte offset of `inner` within `s`, for error reporting
+    let mut offset = s.len() - s.trim_start().len() + 1;
+    let mut vector = Vec::new();
+    for (i, segment) in inner.split(',').enumerate() {
+        match segment.trim().parse::<T>() {
+            Ok(x) => vector.push(x),
+            Err(e) => {
+                return Err(vector_parse_error(format!(
+                    "element {} at byte {}: {:?}: {}",
+                    i, offset, segment, e
+                )))
+            }
+        }
+        offset += segment.len() + 1;
+    }
+    Ok(vector)
+}
+
+/// Decodes a bulk string reply holding `[x1,x2,...]`.
+fn parse_reply<T: str::FromStr<Err = RedisError>>(value: &Value) -> RedisResult<T> {
+    match value {
+        Value::Data(v) => str::from_utf8(v)
+            .map_err(|e| vector_parse_error(format!("invalid utf-8 at byte {}", e.valid_up_to())))?
+            .parse(),
+        Value::Status(s) => s.parse(),
+        _ => Err(vector_parse_error(format!(
+            "expected a bulk string, got {:?}",
+            value
+        ))),
     }
 }
 
-impl From<&[u8]> for Vector {
-    fn from(data: &[u8]) -> Self {
-        let segments: Vec<&[u8]> = data[1..(data.len() - 1)].split(|&x| x == b',').collect();
+impl str::FromStr for Vector {
+    type Err = RedisError;
 
-        let vector: Vec<f32> = segments
-            .iter()
-            .filter_map(|segment| std::str::from_utf8(segment).ok())
-            .filter_map(|segment| segment.parse::<f32>().ok())
-            .collect();
-        Vector { 0: vector }
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_elements(s).map(Vector)
     }
 }
 
 impl FromRedisValue for Vector {
     fn from_redis_value(value: &redis::Value) -> RedisResult<Self> {
-        match value {
-            Value::Data(v) => Ok(Vector::from(v.as_slice())),
-            _ => Err(RedisError::from((ErrorKind::TypeError, ""))),
-        }
+        parse_reply(value)
     }
 }
 
@@ -252,15 +928,169 @@ mod tests {
     use futures::stream::StreamExt;
     use redis::Iter;
     use std::collections::HashMap;
-    use std::env;
+
+    #[test]
+    fn parse_vector() {
+        let v: Vector = "[1,2.5,-3e-2]".parse().unwrap();
+        assert_eq!(v.0, vec![1.0, 2.5, -0.03]);
+        let v: Vector = " [ 1 , 2 ] ".parse().unwrap();
+        assert_eq!(v.0, vec![1.0, 2.0]);
+        let v: Vector = "[]".parse().unwrap();
+        assert!(v.0.is_empty());
+
+        for invalid in ["", "[", "]", "1,2", "[1,2", "[1,,2]", "[1,x]"] {
+            let err = invalid.parse::<Vector>().unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::TypeError);
+        }
+        let err = "[1,2,abc,4]".parse::<Vector>().unwrap_err();
+        assert!(err.to_string().contains("element 2 at byte 5"));
+
+        assert!(Vector::try_from(&b"[1,\xff]"[..]).is_err());
+        assert!(Vector::try_from(&b"[1,2]"[..]).is_ok());
+
+        let v: Vector = redis::from_redis_value(&Value::Data(b"[1,2]".to_vec())).unwrap();
+        assert_eq!(v.0, vec![1.0, 2.0]);
+        assert!(redis::from_redis_value::<Vector>(&Value::Nil).is_err());
+        let v: Option<Vector> = redis::from_redis_value(&Value::Nil).unwrap();
+        assert!(v.is_none());
+        let v: Vec<Option<Vector>> =
+            redis::from_redis_value(&Value::Bulk(vec![Value::Nil, Value::Data(b"[1]".to_vec())]))
+                .unwrap();
+        assert!(v[0].is_none());
+        assert_eq!(v[1].as_ref().unwrap().0, vec![1.0]);
+        assert!(redis::from_redis_value::<Vector>(&Value::Data(b"[1,x]".to_vec())).is_err());
+    }
+
+    #[test]
+    fn encode_vector() {
+        fn encode<T: ToRedisArgs>(v: T) -> Vec<u8> {
+            let args = v.to_redis_args();
+            assert_eq!(args.len(), 1);
+            args.into_iter().next().unwrap()
+        }
+
+        assert_eq!(encode(Vector(vec![1.0, 2.0])), b"[1,2]");
+        assert_eq!(encode(Vector(vec![-0.5, 0.1, 1e-7])), b"[-0.5,0.1,1e-7]");
+        assert_eq!(encode(Vector(vec![])), b"[]");
+        assert_eq!(Vector(vec![1.0, 2.5]).to_string(), "[1,2.5]");
+
+        // encoded straight into the command buffer
+        let mut cmd = redis::cmd("TVS.HSET");
+        cmd.arg(Vector(vec![3.0, 0.25]));
+        assert_eq!(
+            cmd.get_packed_command(),
+            b"*2\r\n$8\r\nTVS.HSET\r\n$8\r\n[3,0.25]\r\n"
+        );
+
+        let mut values = vec![
+            0.0,
+            -0.0,
+            f32::MIN,
+            f32::MAX,
+            f32::MIN_POSITIVE,
+            -f32::MIN_POSITIVE,
+            f32::EPSILON,
+            f32::from_bits(1),
+            f32::from_bits(0x807f_ffff),
+            std::f32::consts::PI,
+            1.0 / 3.0,
+            16777217.0,
+        ];
+        // a spread of bit patterns over the whole finite range
+        let mut bits: u32 = 0x1234_5678;
+        for _ in 0..100_000 {
+            bits = bits.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let x = f32::from_bits(bits);
+            if x.is_finite() {
+                values.push(x);
+            }
+        }
+        let encoded = encode(Vector::new(values.clone()).unwrap());
+        let decoded = Vector::try_from(encoded.as_slice()).unwrap();
+        assert_eq!(decoded.0.len(), values.len());
+        for (x, y) in values.iter().zip(decoded.0.iter()) {
+            assert_eq!(x.to_bits(), y.to_bits());
+        }
+
+        for invalid in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            let err = Vector::new(vec![1.0, invalid]).unwrap_err();
+            assert!(err.to_string().contains("element 1"));
+        }
+    }
+
+    #[test]
+    fn as_vector() {
+        fn encode<V: AsVector>(v: V) -> String {
+            VectorArg(v).to_string()
+        }
+
+        let values = vec![1.0f32, -2.5];
+        assert_eq!(encode(&values[..]), "[1,-2.5]");
+        assert_eq!(encode(&values), "[1,-2.5]");
+        assert_eq!(encode(values.clone()), "[1,-2.5]");
+        assert_eq!(encode([1.0f32, -2.5]), "[1,-2.5]");
+        assert_eq!(encode(Cow::Borrowed(&values[..])), "[1,-2.5]");
+        assert_eq!(
+            encode(Vector::try_from(values.clone()).unwrap()),
+            "[1,-2.5]"
+        );
+        #[cfg(feature = "ndarray")]
+        {
+            let array = Array1::from(values.clone());
+            assert_eq!(encode(array.view()), "[1,-2.5]");
+            assert_eq!(encode(&array), "[1,-2.5]");
+        }
+
+        let v = Vector::try_from(values.clone()).unwrap();
+        assert_eq!(v.len(), 2);
+        assert_eq!(v[1], -2.5);
+        assert_eq!(v.into_inner(), values);
+
+        assert!(Vector::try_from(vec![1.0, f32::NAN]).is_err());
+        assert!([1.0f32, f32::INFINITY].check().is_err());
+        assert_eq!(
+            VectorArg([f32::NAN]).to_redis_args(),
+            [b"invalid vector: element 0 is not finite: NaN".to_vec()]
+        );
+    }
+
+    #[test]
+    fn non_finite_vectors() {
+        let client = redis::Client::open(crate::mock::test_url()).unwrap();
+        let mut conn = client.get_connection().unwrap();
+        let index_name = "test-non-finite";
+        let _: RedisResult<bool> = conn.tvs_del_index(index_name);
+        let _: bool = conn.tvs_create_index(index_name, 2, "FLAT", "L2").unwrap();
+
+        let err = conn
+            .tvs_hset_vector::<_, _, _, usize>(index_name, "k1", &[f32::NAN, 1.0][..])
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ClientError);
+        assert!(matches!(
+            TairVectorError::from(err),
+            TairVectorError::InvalidParameter(m) if m == "element 0 is not finite: NaN"
+        ));
+        assert!(conn
+            .tvs_knnsearch::<_, _, Vec<(String, f32)>>(index_name, 1, vec![0.0, f32::INFINITY])
+            .is_err());
+        let stored: Vec<Option<Vector>> = conn.tvs_get_vector(index_name, "k1").unwrap();
+        assert!(stored.iter().all(Option::is_none));
+
+        // the pipeline still gets one reply per command
+        let result: RedisResult<(usize, usize)> = redis::pipe()
+            .tvs_hset_vector(index_name, "k1", [1.0f32, 1.0])
+            .tvs_hset_vector(index_name, "k2", [f32::NAN, 1.0])
+            .query(&mut conn);
+        assert!(result.is_err());
+        let stored: Vec<Option<Vector>> = conn.tvs_get_vector(index_name, "k2").unwrap();
+        assert!(stored.iter().all(Option::is_none));
+
+        let _: bool = conn.tvs_del_index(index_name).unwrap();
+    }
 
     #[test]
     fn sync_ops() {
-        let redis_url = if let Ok(v) = env::var("TAIR_URL") {
-            v
-        } else {
-            String::from("redis://127.0.0.1/")
-        };
+        let redis_url = crate::mock::test_url();
 
         let index_name = "test-sync-ops";
         let client = redis::Client::open(redis_url).unwrap();
@@ -286,7 +1116,7 @@ mod tests {
         // TVS.SCANINDEX
         let iter: Iter<String> = conn.tvs_scan_index().unwrap();
         let scanned_indices: Vec<String> = iter.collect();
-        assert!(scanned_indices.len() >= 1);
+        assert!(!scanned_indices.is_empty());
         let mut found = false;
         for name in scanned_indices {
             if name == index_name {
@@ -302,7 +1132,7 @@ mod tests {
 
         // TVS.GETINDEX
         let index_info: HashMap<String, String> = conn.tvs_get_index(index_name).unwrap();
-        assert!(index_info.len() > 0);
+        assert!(!index_info.is_empty());
         for (k, v) in params {
             assert_eq!(index_info.get(k).unwrap().to_owned(), v.to_string());
         }
@@ -315,12 +1145,12 @@ mod tests {
         assert!(created);
 
         // TVS.HSET
-        let vector: Vector = Vector { 0: vec![1.0, 2.0] };
+        let vector: Vector = Vector(vec![1.0, 2.0]);
         let count: usize = conn
             .tvs_hset_multi(
                 index_name,
                 "k1",
-                &vec![("attr1", "val1"), ("VECTOR", &vector.to_string())],
+                &[("attr1", "val1"), ("VECTOR", &vector.to_string())],
             )
             .unwrap();
         assert_eq!(count, 2);
@@ -351,23 +1181,217 @@ mod tests {
 
         // TVS.KNNSEARCH
         let knn_results: Vec<(String, f32)> = conn
-            .tvs_knnsearch_with_params(index_name, 10, &vector, &vec![("ef_search", 100)])
+            .tvs_knnsearch_with_params(index_name, 10, &vector, &[("ef_search", 100)])
             .unwrap();
         assert_eq!(knn_results.len(), 1);
         assert_eq!(knn_results[0].0, "k1");
         assert_eq!(knn_results[0].1, 0.0);
 
+        let knn_result: KnnResult = conn.tvs_knnsearch(index_name, 10, &vector).unwrap();
+        assert_eq!(knn_result.len(), 1);
+        assert_eq!(knn_result[0].key, "k1");
+        assert_eq!(knn_result[0].distance, 0.0);
+
+        // TVS.GETDISTANCE
+        let distances: Vec<(String, f32)> = conn
+            .tvs_get_distance(index_name, &vector, &["k1"], None, None, None::<&str>)
+            .unwrap();
+        assert_eq!(distances.len(), 1);
+        assert_eq!(distances[0].0, "k1");
+        assert_eq!(distances[0].1, 0.0);
+
+        let distances: Vec<(String, f32)> = conn
+            .tvs_get_distance(
+                index_name,
+                Vector(vec![1.0, 3.0]),
+                &["k1"],
+                Some(1),
+                Some(0.5),
+                Some("attr1 == \"val1\""),
+            )
+            .unwrap();
+        assert!(distances.is_empty());
+
+        // TVS.MKNNSEARCH
+        let mknn_results: Vec<Vec<(String, f32)>> = conn
+            .tvs_mknnsearch(
+                index_name,
+                10,
+                &[Vector(vec![1.0, 2.0]), Vector(vec![3.0, 4.0])],
+            )
+            .unwrap();
+        assert_eq!(mknn_results.len(), 2);
+        assert_eq!(mknn_results[0].len(), 1);
+        assert_eq!(mknn_results[0][0].0, "k1");
+        assert_eq!(mknn_results[0][0].1, 0.0);
+        assert_eq!(mknn_results[1][0].0, "k1");
+
+        let mknn_results: Vec<Vec<(String, f32)>> = conn
+            .tvs_mknnsearch(index_name, 10, &[vec![1.0, 2.0], vec![3.0, 4.0]])
+            .unwrap();
+        assert_eq!(mknn_results.len(), 2);
+        assert_eq!(mknn_results[1][0].0, "k1");
+        let err = conn
+            .tvs_mknnsearch::<_, _, Vec<Vec<(String, f32)>>>(
+                index_name,
+                10,
+                &[vec![1.0, 2.0], vec![3.0]],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.detail(),
+            Some("vector 1 has 1 elements, vector 0 has 2")
+        );
+
+        let mknn_results: Vec<Vec<(String, f32)>> = conn
+            .tvs_mknnsearch_with_params(
+                index_name,
+                10,
+                &[&vector],
+                Some("attr1 == \"val2\""),
+                &[("ef_search", 100)],
+            )
+            .unwrap();
+        assert_eq!(mknn_results.len(), 1);
+        assert!(mknn_results[0].is_empty());
+
+        // TVS.MINDEXKNNSEARCH
+        let index_name2 = "test-sync-ops-2";
+        conn.tvs_del_index::<_, usize>(index_name2).unwrap();
+        let created: bool = conn.tvs_create_index(index_name2, 2, "FLAT", "L2").unwrap();
+        assert!(created);
+        let count: usize = conn
+            .tvs_hset_vector(index_name2, "k2", Vector(vec![2.0, 2.0]))
+            .unwrap();
+        assert_eq!(count, 1);
+
+        let knn_results: Vec<(String, f32)> = conn
+            .tvs_mindex_knnsearch(
+                &[index_name, index_name2],
+                10,
+                &vector,
+                &[("ef_search", 100)],
+            )
+            .unwrap();
+        assert_eq!(knn_results.len(), 2);
+        assert_eq!(knn_results[0].0, "k1");
+        assert_eq!(knn_results[0].1, 0.0);
+        assert_eq!(knn_results[1].0, "k2");
+
+        // TVS.MINDEXMKNNSEARCH
+        let mknn_results: Vec<Vec<(String, f32)>> = conn
+            .tvs_mindex_mknnsearch(
+                &[index_name, index_name2],
+                1,
+                &[Vector(vec![1.0, 2.0]), Vector(vec![2.0, 2.0])],
+                &[("ef_search", 100)],
+            )
+            .unwrap();
+        assert_eq!(mknn_results.len(), 2);
+        assert_eq!(mknn_results[0][0].0, "k1");
+        assert_eq!(mknn_results[1][0].0, "k2");
+
+        conn.tvs_del_index::<_, usize>(index_name2).unwrap();
+
+        // TVS.HDEL
+        let deleted: usize = conn.tvs_hdel(index_name, "k1", &["attr1"]).unwrap();
+        assert_eq!(deleted, 1);
+        let got: HashMap<String, String> = conn.tvs_hgetall(index_name, "k1").unwrap();
+        assert_eq!(got.len(), 1);
+
+        // TVS.DEL
+        let count: usize = conn.tvs_hset_vector(index_name, "k2", &vector).unwrap();
+        assert_eq!(count, 1);
+        let deleted: usize = conn.tvs_del(index_name, &["k1", "k2", "k3"]).unwrap();
+        assert_eq!(deleted, 2);
+        let key_iter: Iter<String> = conn.tvs_scan(index_name).unwrap();
+        assert_eq!(key_iter.count(), 0);
+
+        conn.tvs_del_index::<_, usize>(index_name).unwrap();
+    }
+
+    #[test]
+    fn text_search() {
+        let redis_url = crate::mock::test_url();
+
+        let index_name = "test-text-search";
+        let client = redis::Client::open(redis_url).unwrap();
+        let mut conn = client.get_connection().unwrap();
+
+        // cleanup
+        conn.tvs_del_index::<_, usize>(index_name).unwrap();
+
+        let spec = IndexSpec::new(index_name, 2, IndexType::Hnsw, DistanceType::Ip)
+            .lexical_algorithm(LexicalAlgorithm::Bm25)
+            .analyzer("jieba");
+        let created: bool = conn.tvs_create_index_spec(&spec).unwrap();
+        assert!(created);
+
+        let index_info: IndexInfo = conn.tvs_get_index(index_name).unwrap();
+        assert_eq!(index_info.lexical_algorithm, Some(LexicalAlgorithm::Bm25));
+
+        let v1 = Vector::new(vec![1.0, 0.0]).unwrap();
+        let v2 = Vector::new(vec![0.0, 1.0]).unwrap();
+        let _: usize = conn
+            .tvs_hset_hybrid(index_name, "k1", &v1, "red apple", &[("price", 10)])
+            .unwrap();
+        let _: usize = conn
+            .tvs_hset_hybrid(index_name, "k2", &v2, "green pear", &[("price", 20)])
+            .unwrap();
+
+        // the text query outweighs the vector, which is closer to k2
+        let results: KnnResult = conn
+            .tvs_text_knnsearch(
+                index_name,
+                2,
+                Vector::new(vec![0.0, 1.0]).unwrap(),
+                "apple",
+                0.1,
+                &[("ef_search", 100)],
+            )
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].key, "k1");
+
+        conn.tvs_del_index::<_, usize>(index_name).unwrap();
+    }
+
+    #[test]
+    fn pipeline_ops() {
+        let redis_url = crate::mock::test_url();
+
+        let index_name = "test-pipeline-ops";
+        let client = redis::Client::open(redis_url).unwrap();
+        let mut conn = client.get_connection().unwrap();
+
+        // cleanup
+        conn.tvs_del_index::<_, usize>(index_name).unwrap();
+
+        // TVS.CREATEINDEX, TVS.HSET and TVS.KNNSEARCH in one round trip
+        let vector1 = Vector(vec![1.0, 2.0]);
+        let vector2 = Vector(vec![3.0, 4.0]);
+        let (created, count1, count2, knn_results): (bool, usize, usize, Vec<(String, f32)>) =
+            redis::pipe()
+                .tvs_create_index(index_name, 2, "FLAT", "L2")
+                .tvs_hset_vector(index_name, "k1", &vector1)
+                .tvs_hset(index_name, "k2", "VECTOR", &vector2)
+                .tvs_knnsearch(index_name, 10, &vector1)
+                .query(&mut conn)
+                .unwrap();
+        assert!(created);
+        assert_eq!(count1, 1);
+        assert_eq!(count2, 1);
+        assert_eq!(knn_results.len(), 2);
+        assert_eq!(knn_results[0].0, "k1");
+        assert_eq!(knn_results[0].1, 0.0);
+
         conn.tvs_del_index::<_, usize>(index_name).unwrap();
     }
 
     #[cfg(feature = "aio")]
     #[tokio::test]
     async fn async_ops() {
-        let redis_url = if let Ok(v) = env::var("TAIR_URL") {
-            v
-        } else {
-            String::from("redis://127.0.0.1/")
-        };
+        let redis_url = crate::mock::test_url();
 
         let index_name = "test-async-ops";
         let client = redis::Client::open(redis_url).unwrap();
@@ -394,7 +1418,7 @@ mod tests {
         // TVS.SCANINDEX
         let iter: AsyncIter<String> = conn.tvs_scan_index().await.unwrap();
         let scanned_indices: Vec<String> = iter.collect().await;
-        assert!(scanned_indices.len() >= 1);
+        assert!(!scanned_indices.is_empty());
         let mut found = false;
         for name in scanned_indices {
             if name == index_name {
@@ -410,7 +1434,7 @@ mod tests {
 
         // TVS.GETINDEX
         let index_info: HashMap<String, String> = conn.tvs_get_index(index_name).await.unwrap();
-        assert!(index_info.len() > 0);
+        assert!(!index_info.is_empty());
         for (k, v) in params {
             assert_eq!(index_info.get(k).unwrap().to_owned(), v.to_string());
         }
@@ -426,12 +1450,12 @@ mod tests {
         assert!(created);
 
         // TVS.HSET
-        let vector: Vector = Vector { 0: vec![1.0, 2.0] };
+        let vector: Vector = Vector(vec![1.0, 2.0]);
         let count: usize = conn
             .tvs_hset_multi(
                 index_name,
                 "k1",
-                &vec![("attr1", "val1"), ("VECTOR", &vector.to_string())],
+                &[("attr1", "val1"), ("VECTOR", &vector.to_string())],
             )
             .await
             .unwrap();
@@ -464,13 +1488,32 @@ mod tests {
 
         // TVS.KNNSEARCH
         let knn_results: Vec<(String, f32)> = conn
-            .tvs_knnsearch_with_params(index_name, 10, &vector, &vec![("ef_search", 100)])
+            .tvs_knnsearch_with_params(index_name, 10, &vector, &[("ef_search", 100)])
             .await
             .unwrap();
         assert_eq!(knn_results.len(), 1);
         assert_eq!(knn_results[0].0, "k1");
         assert_eq!(knn_results[0].1, 0.0);
 
+        // pipeline
+        let (count, knn_results): (usize, Vec<(String, f32)>) = redis::pipe()
+            .tvs_hset_vector(index_name, "k2", Vector(vec![3.0, 4.0]))
+            .tvs_knnsearch(index_name, 10, &vector)
+            .query_async(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(knn_results.len(), 2);
+        assert_eq!(knn_results[1].0, "k2");
+
+        // TVS.HDEL
+        let deleted: usize = conn.tvs_hdel(index_name, "k1", &["attr1"]).await.unwrap();
+        assert_eq!(deleted, 1);
+
+        // TVS.DEL
+        let deleted: usize = conn.tvs_del(index_name, &["k1", "k2", "k3"]).await.unwrap();
+        assert_eq!(deleted, 2);
+
         conn.tvs_del_index::<_, usize>(index_name).await.unwrap();
     }
 }
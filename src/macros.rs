@@ -1,10 +1,13 @@
+// The optional `check(...)` expressions of a command return a
+// `RedisResult<()>` and are evaluated before it is sent. Pipelines cannot
+// fail while queuing, so they skip them.
 macro_rules! implement_commands {
     (
         $lifetime: lifetime
         $(
             $(#[$attr:meta])+
             fn $name:ident<$($tyargs:ident : $ty:ident),*>(
-                $($argname:ident: $argty:ty),*) $body:block
+                $($argname:ident: $argty:ty),*) $(check($($check:expr),+))? $body:block
         )*
     ) =>
     (
@@ -16,21 +19,56 @@ macro_rules! implement_commands {
                 fn $name<$lifetime, $($tyargs: $ty, )* RV: FromRedisValue>(
                     &mut self $(, $argname: $argty)*) -> RedisResult<RV>
                     // { redis::Cmd::$name($($argname),*).query(self) }
-                    { ($body).query(self) }
+                    {
+                        $($($check?;)+)?
+                        ($body).query(self)
+                    }
             )*
 
+            /// TVS.GETINDEX index_name, with `None` for a missing index
+            #[inline]
+            fn tvs_get_index_info<K: ToRedisArgs>(&mut self, index_name: K) -> RedisResult<Option<IndexInfo>> {
+                let reply: Value = self.tvs_get_index(index_name)?;
+                IndexInfo::from_reply(&reply)
+            }
+
+            /// Creates the index of `spec` unless it exists. An existing index
+            /// must match `spec`, or a `SchemaMismatch` error lists the
+            /// differing fields.
+            fn tvs_ensure_index(&mut self, spec: &IndexSpec) -> Result<EnsureIndex, TairVectorError> {
+                let existing = self.tvs_get_index_info(&spec.name)?;
+                if let Some(info) = existing {
+                    return spec.verify(&info);
+                }
+                match self.tvs_create_index_spec::<bool>(spec).map_err(TairVectorError::from) {
+                    Ok(_) => Ok(EnsureIndex::Created),
+                    // another client created it in the meantime
+                    Err(TairVectorError::IndexAlreadyExists(_)) => {
+                        let existing = self.tvs_get_index_info(&spec.name)?;
+                        let info = existing
+                            .ok_or_else(|| TairVectorError::IndexNotFound(spec.name.clone()))?;
+                        spec.verify(&info)
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+
+            /// TVS.SCANINDEX, failing on cluster connections
             #[inline]
             fn tvs_scan_index<K: FromRedisValue>(&mut self) -> RedisResult<Iter<'_, K>> {
+                check_single_node::<Self>(SCAN_INDEX_ON_CLUSTER)?;
                 let mut c = redis::cmd("TVS.SCANINDEX");
                 c.cursor_arg(0);
                 c.iter(self)
             }
 
+            /// TVS.SCANINDEX MATCH pattern, failing on cluster connections
             #[inline]
             fn tvs_scan_index_match<P: ToRedisArgs, K: FromRedisValue>(
                 &mut self,
                 pattern: P,
             ) -> RedisResult<Iter<'_, K>> {
+                check_single_node::<Self>(SCAN_INDEX_ON_CLUSTER)?;
                 let mut c = redis::cmd("TVS.SCANINDEX");
                 c.arg(0).arg("MATCH").arg(pattern);
                 c.iter(self)
@@ -58,28 +96,30 @@ macro_rules! implement_commands {
             }
 
             #[inline]
-            fn tvs_scan_max_dist<K: ToRedisArgs, V: ToRedisArgs, D: ToRedisArgs, RK: FromRedisValue>(
+            fn tvs_scan_max_dist<K: ToRedisArgs, V: AsVector + ?Sized, D: ToRedisArgs, RK: FromRedisValue>(
                 &mut self,
                 index_name: K,
                 vector: &V,
                 max_dist: D,
             ) -> RedisResult<Iter<'_, RK>> {
+                vector.check()?;
                 let mut c = redis::cmd("TVS.SCAN");
                 c.arg(index_name)
                     .cursor_arg(0)
                     .arg("VECTOR")
-                    .arg(vector)
+                    .arg(VectorArg(vector))
                     .arg("MAX_DIST")
                     .arg(max_dist);
                 c.iter(self)
             }
 
             #[inline]
-            fn tvs_scan_filter<K: ToRedisArgs, F: ToRedisArgs, RK: FromRedisValue>(
+            fn tvs_scan_filter<K: ToRedisArgs, F: AsFilter, RK: FromRedisValue>(
                 &mut self,
                 index_name: K,
                 filter: F,
             ) -> RedisResult<Iter<'_, RK>> {
+                filter.check()?;
                 let mut c = redis::cmd("TVS.SCAN");
                 c.arg(index_name).cursor_arg(0).arg("FILTER").arg(filter);
                 c.iter(self)
@@ -89,9 +129,9 @@ macro_rules! implement_commands {
             fn tvs_scan_full<
                 K: ToRedisArgs,
                 P: ToRedisArgs,
-                V: ToRedisArgs,
+                V: AsVector,
                 D: ToRedisArgs,
-                F: ToRedisArgs,
+                F: AsFilter,
                 RK: FromRedisValue,
             >(
                 &mut self,
@@ -105,9 +145,11 @@ macro_rules! implement_commands {
                     c.arg("MATCH").arg(p);
                 }
                 if let Some((v, d)) = max_dist {
-                    c.arg("VECTOR").arg(v).arg("MAX_DIST").arg(d);
+                    v.check()?;
+                    c.arg("VECTOR").arg(VectorArg(v)).arg("MAX_DIST").arg(d);
                 }
                 if let Some(f) = filter {
+                    f.check()?;
                     c.arg("FILTER").arg(f);
                 }
 
@@ -116,6 +158,25 @@ macro_rules! implement_commands {
 
         }
 
+        /// Queues TairVector commands on a pipeline so that they can be sent
+        /// to the server in one round trip. Works with both `query` and
+        /// `query_async`.
+        pub trait TairVectorPipeline : Sized {
+            /// Appends a raw command to the pipeline.
+            fn add_tvs_command(&mut self, cmd: redis::Cmd) -> &mut Self;
+
+            $(
+                $(#[$attr])*
+                #[inline]
+                #[allow(clippy::extra_unused_lifetimes, clippy::needless_lifetimes)]
+                fn $name<$lifetime, $($tyargs: $ty),*>(
+                    &mut self $(, $argname: $argty)*
+                ) -> &mut Self {
+                    self.add_tvs_command(::std::mem::take($body))
+                }
+            )*
+        }
+
         #[cfg(feature = "aio")]
         pub trait TairVectorAsyncCommands : redis::aio::ConnectionLike + Send + Sized {
             $(
@@ -129,22 +190,75 @@ macro_rules! implement_commands {
                 where
                     RV: FromRedisValue,
                 {
-                    Box::pin(async move { ($body).query_async(self).await })
+                    Box::pin(async move {
+                        $($($check?;)+)?
+                        ($body).query_async(self).await
+                    })
                 }
             )*
 
+            /// TVS.GETINDEX index_name, with `None` for a missing index
             #[inline]
-            fn tvs_scan_index<K: FromRedisValue>(&mut self) -> RedisFuture<AsyncIter<'_, K>> {
+            fn tvs_get_index_info<'a, K: ToRedisArgs + Send + Sync + 'a>(
+                &'a mut self,
+                index_name: K,
+            ) -> RedisFuture<'a, Option<IndexInfo>> {
+                Box::pin(async move {
+                    let reply: Value = self.tvs_get_index(index_name).await?;
+                    IndexInfo::from_reply(&reply)
+                })
+            }
+
+            /// Creates the index of `spec` unless it exists. An existing index
+            /// must match `spec`, or a `SchemaMismatch` error lists the
+            /// differing fields.
+            fn tvs_ensure_index<'a>(
+                &'a mut self,
+                spec: &'a IndexSpec,
+            ) -> TairVectorFuture<'a, EnsureIndex> {
+                Box::pin(async move {
+                    let existing = self.tvs_get_index_info(&spec.name).await?;
+                    if let Some(info) = existing {
+                        return spec.verify(&info);
+                    }
+                    match self
+                        .tvs_create_index_spec::<bool>(spec)
+                        .await
+                        .map_err(TairVectorError::from)
+                    {
+                        Ok(_) => Ok(EnsureIndex::Created),
+                        // another client created it in the meantime
+                        Err(TairVectorError::IndexAlreadyExists(_)) => {
+                            let existing = self.tvs_get_index_info(&spec.name).await?;
+                            let info = existing
+                                .ok_or_else(|| TairVectorError::IndexNotFound(spec.name.clone()))?;
+                            spec.verify(&info)
+                        }
+                        Err(e) => Err(e),
+                    }
+                })
+            }
+
+            /// TVS.SCANINDEX, failing on cluster connections
+            #[inline]
+            fn tvs_scan_index<K: FromRedisValue>(&mut self) -> RedisFuture<'_, AsyncIter<'_, K>> {
+                if let Err(e) = check_single_node::<Self>(SCAN_INDEX_ON_CLUSTER) {
+                    return Box::pin(async move { Err(e) });
+                }
                 let mut c = redis::cmd("TVS.SCANINDEX");
                 c.cursor_arg(0);
                 Box::pin(async move { c.iter_async(self).await })
             }
 
+            /// TVS.SCANINDEX MATCH pattern, failing on cluster connections
             #[inline]
             fn tvs_scan_index_match<P: ToRedisArgs, K: FromRedisValue>(
                 &mut self,
                 pattern: P,
-            ) -> RedisFuture<AsyncIter<'_, K>> {
+            ) -> RedisFuture<'_, AsyncIter<'_, K>> {
+                if let Err(e) = check_single_node::<Self>(SCAN_INDEX_ON_CLUSTER) {
+                    return Box::pin(async move { Err(e) });
+                }
                 let mut c = redis::cmd("TVS.SCANINDEX");
                 c.arg(0).arg("MATCH").arg(pattern);
                 Box::pin(async move { c.iter_async(self).await })
@@ -154,7 +268,7 @@ macro_rules! implement_commands {
             fn tvs_scan<K: ToRedisArgs, RK: FromRedisValue>(
                 &mut self,
                 index_name: K,
-            ) -> RedisFuture<AsyncIter<'_, RK>> {
+            ) -> RedisFuture<'_, AsyncIter<'_, RK>> {
                 let mut c = redis::cmd("TVS.SCAN");
                 c.arg(index_name).cursor_arg(0);
                 Box::pin(async move { c.iter_async(self).await })
@@ -165,35 +279,41 @@ macro_rules! implement_commands {
                 &mut self,
                 index_name: K,
                 pattern: P,
-            ) -> RedisFuture<AsyncIter<'_, RK>> {
+            ) -> RedisFuture<'_, AsyncIter<'_, RK>> {
                 let mut c = redis::cmd("TVS.SCAN");
                 c.arg(index_name).cursor_arg(0).arg("MATCH").arg(pattern);
                 Box::pin(async move { c.iter_async(self).await })
             }
 
             #[inline]
-            fn tvs_scan_max_dist<K: ToRedisArgs, V: ToRedisArgs, D: ToRedisArgs, RK: FromRedisValue>(
+            fn tvs_scan_max_dist<K: ToRedisArgs, V: AsVector + ?Sized, D: ToRedisArgs, RK: FromRedisValue>(
                 &mut self,
                 index_name: K,
                 vector: &V,
                 max_dist: D,
-            ) -> RedisFuture<AsyncIter<'_, RK>> {
+            ) -> RedisFuture<'_, AsyncIter<'_, RK>> {
+                if let Err(e) = vector.check() {
+                    return Box::pin(async move { Err(e) });
+                }
                 let mut c = redis::cmd("TVS.SCAN");
                 c.arg(index_name)
                     .cursor_arg(0)
                     .arg("VECTOR")
-                    .arg(vector)
+                    .arg(VectorArg(vector))
                     .arg("MAX_DIST")
                     .arg(max_dist);
                 Box::pin(async move { c.iter_async(self).await })
             }
 
             #[inline]
-            fn tvs_scan_filter<K: ToRedisArgs, F: ToRedisArgs, RK: FromRedisValue>(
+            fn tvs_scan_filter<K: ToRedisArgs, F: AsFilter, RK: FromRedisValue>(
                 &mut self,
                 index_name: K,
                 filter: F,
-            ) -> RedisFuture<AsyncIter<'_, RK>> {
+            ) -> RedisFuture<'_, AsyncIter<'_, RK>> {
+                if let Err(e) = filter.check() {
+                    return Box::pin(async move { Err(e) });
+                }
                 let mut c = redis::cmd("TVS.SCAN");
                 c.arg(index_name).cursor_arg(0).arg("FILTER").arg(filter);
                 Box::pin(async move { c.iter_async(self).await })
@@ -203,9 +323,9 @@ macro_rules! implement_commands {
             fn tvs_scan_full<
                 K: ToRedisArgs,
                 P: ToRedisArgs,
-                V: ToRedisArgs,
+                V: AsVector,
                 D: ToRedisArgs,
-                F: ToRedisArgs,
+                F: AsFilter,
                 RK: FromRedisValue,
             >(
                 &mut self,
@@ -213,15 +333,21 @@ macro_rules! implement_commands {
                 pattern: Option<P>,
                 max_dist: Option<(V, D)>,
                 filter: Option<F>,
-            ) -> RedisFuture<AsyncIter<'_, RK>> {
+            ) -> RedisFuture<'_, AsyncIter<'_, RK>> {
                 let mut c = redis::cmd("TVS.SCAN").arg(index_name).cursor_arg(0).clone();
                 if let Some(p) = pattern {
                     c.arg("MATCH").arg(p);
                 }
                 if let Some((v, d)) = max_dist {
-                    c.arg("VECTOR").arg(v).arg("MAX_DIST").arg(d);
+                    if let Err(e) = v.check() {
+                        return Box::pin(async move { Err(e) });
+                    }
+                    c.arg("VECTOR").arg(VectorArg(v)).arg("MAX_DIST").arg(d);
                 }
                 if let Some(f) = filter {
+                    if let Err(e) = f.check() {
+                        return Box::pin(async move { Err(e) });
+                    }
                     c.arg("FILTER").arg(f);
                 }
 
@@ -0,0 +1,770 @@
+use super::filter::Expr;
+use crate::{DataType, DistanceType, IndexType, LexicalAlgorithm, SparseVector, Vector};
+use redis::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::str::FromStr;
+
+/// A reply, or the error line sent to the client.
+pub(crate) type Reply = Result<Value, String>;
+
+/// The indices of a mock server and the TVS.* commands operating on them.
+#[derive(Debug, Default)]
+pub(crate) struct Engine {
+    indices: BTreeMap<String, Index>,
+    /// The CLUSTER SLOTS reply, for servers posing as cluster masters
+    pub(crate) cluster_slots: Option<Value>,
+}
+
+#[derive(Debug)]
+struct Index {
+    dim: usize,
+    index_type: IndexType,
+    distance_type: DistanceType,
+    data_type: DataType,
+    lexical_algorithm: Option<LexicalAlgorithm>,
+    /// Creation parameters, reported back by TVS.GETINDEX.
+    params: Vec<(String, String)>,
+    entries: BTreeMap<String, Entry>,
+    deleted: u64,
+}
+
+#[derive(Debug, Default)]
+struct Entry {
+    fields: BTreeMap<String, String>,
+    vector: Option<Vec<f32>>,
+}
+
+impl Engine {
+    pub(crate) fn execute(&mut self, args: &[Vec<u8>]) -> Reply {
+        let args = args
+            .iter()
+            .map(|a| String::from_utf8(a.clone()).map_err(|_| "ERR invalid utf-8".to_owned()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (command, items) = args
+            .split_first()
+            .ok_or_else(|| "ERR empty command".to_owned())?;
+        let mut args = Args { command, items };
+
+        let reply = match command.to_ascii_uppercase().as_str() {
+            "PING" => Ok(Value::Status("PONG".to_owned())),
+            "CLUSTER" => self.cluster(&mut args),
+            "TVS.CREATEINDEX" => self.create_index(&mut args),
+            "TVS.GETINDEX" => self.get_index(&mut args),
+            "TVS.DELINDEX" => self.del_index(&mut args),
+            "TVS.SCANINDEX" => self.scan_index(&mut args),
+            "TVS.HSET" => self.hset(&mut args),
+            "TVS.HGETALL" => self.hgetall(&mut args),
+            "TVS.HMGET" => self.hmget(&mut args),
+            "TVS.DEL" => self.del(&mut args),
+            "TVS.HDEL" => self.hdel(&mut args),
+            "TVS.SCAN" => self.scan(&mut args),
+            "TVS.KNNSEARCH" => self.knnsearch(&mut args, false),
+            "TVS.KNNSEARCHFIELD" => self.knnsearch(&mut args, true),
+            "TVS.MKNNSEARCH" => self.mknnsearch(&mut args),
+            "TVS.MINDEXKNNSEARCH" => self.mindex_knnsearch(&mut args),
+            "TVS.MINDEXMKNNSEARCH" => self.mindex_mknnsearch(&mut args),
+            "TVS.GETDISTANCE" => self.get_distance(&mut args),
+            _ => return Err(format!("ERR unknown command '{}'", command)),
+        }?;
+        if !args.is_empty() {
+            return Err(args.wrong_arity());
+        }
+        Ok(reply)
+    }
+
+    fn index(&self, name: &str) -> Result<&Index, String> {
+        self.indices.get(name).ok_or_else(index_not_found)
+    }
+
+    fn index_mut(&mut self, name: &str) -> Result<&mut Index, String> {
+        self.indices.get_mut(name).ok_or_else(index_not_found)
+    }
+
+    fn create_index(&mut self, args: &mut Args) -> Reply {
+        let name = args.next()?;
+        let dim: usize = args.parse("dimension")?;
+        let index_type: IndexType = args.parse("index type")?;
+        let distance_type: DistanceType = args.parse("distance type")?;
+        let mut index = Index {
+            dim,
+            index_type,
+            distance_type,
+            data_type: DataType::Float32,
+            lexical_algorithm: None,
+            params: vec![],
+            entries: BTreeMap::new(),
+            deleted: 0,
+        };
+        if index_type == IndexType::Hnsw {
+            index.set_param("ef_construct", "100");
+            index.set_param("M", "16");
+        }
+        while !args.is_empty() {
+            let (key, value) = (args.next()?, args.next()?);
+            match key.to_ascii_lowercase().as_str() {
+                "data_type" => index.data_type = parse(value, key)?,
+                "lexical_algorithm" => index.lexical_algorithm = Some(parse(value, key)?),
+                "ef_construct" | "m" => {
+                    parse::<usize>(value, key)?;
+                }
+                "auto_gc" => {
+                    parse::<bool>(value, key)?;
+                }
+                _ => {}
+            }
+            index.set_param(key, value);
+        }
+
+        if dim == 0 {
+            return Err("ERR invalid dimension: 0".to_owned());
+        }
+        if (distance_type == DistanceType::Jaccard) != (index.data_type == DataType::Binary) {
+            return Err(format!(
+                "ERR distance method {} does not support data type {}",
+                distance_type, index.data_type
+            ));
+        }
+        if self.indices.contains_key(name) {
+            return Err("ERR index already exists".to_owned());
+        }
+        self.indices.insert(name.to_owned(), index);
+        Ok(Value::Okay)
+    }
+
+    fn get_index(&mut self, args: &mut Args) -> Reply {
+        let index = match self.indices.get(args.next()?) {
+            Some(index) => index,
+            // like Tair, an empty array rather than nil
+            None => return Ok(Value::Bulk(vec![])),
+        };
+        let count = index.entries.len().to_string();
+        let mut fields = vec![
+            ("dimension", index.dim.to_string()),
+            ("algorithm", index.index_type.to_string()),
+            ("distance_method", index.distance_type.to_string()),
+            ("data_type", index.data_type.to_string()),
+            ("data_count", count.clone()),
+            ("current_record_count", count),
+            ("delete_record_count", index.deleted.to_string()),
+        ];
+        for (key, value) in &index.params {
+            if !key.eq_ignore_ascii_case("data_type") {
+                fields.push((key, value.clone()));
+            }
+        }
+        Ok(Value::Bulk(
+            fields
+                .into_iter()
+                .flat_map(|(k, v)| [data(k), data(v)])
+                .collect(),
+        ))
+    }
+
+    fn del_index(&mut self, args: &mut Args) -> Reply {
+        let removed = self.indices.remove(args.next()?).is_some();
+        Ok(Value::Int(removed as i64))
+    }
+
+    fn cluster(&mut self, args: &mut Args) -> Reply {
+        match args.next()?.to_ascii_uppercase().as_str() {
+            "SLOTS" => self
+                .cluster_slots
+                .clone()
+                .ok_or_else(|| "ERR This instance has cluster support disabled".to_owned()),
+            _ => Err("ERR syntax error".to_owned()),
+        }
+    }
+
+    fn scan_index(&mut self, args: &mut Args) -> Reply {
+        let _cursor: u64 = args.parse("cursor")?;
+        let mut pattern = None;
+        while !args.is_empty() {
+            match args.next()?.to_ascii_uppercase().as_str() {
+                "MATCH" => pattern = Some(args.next()?),
+                "COUNT" => {
+                    args.parse::<usize>("count")?;
+                }
+                _ => return Err("ERR syntax error".to_owned()),
+            }
+        }
+        let names = self
+            .indices
+            .keys()
+            .filter(|name| pattern.map_or(true, |p| glob_match(p.as_bytes(), name.as_bytes())));
+        Ok(scan_reply(names))
+    }
+
+    fn hset(&mut self, args: &mut Args) -> Reply {
+        let index = self.index_mut(args.next()?)?;
+        let key = args.next()?;
+        if args.is_empty() || args.items.len() % 2 != 0 {
+            return Err(args.wrong_arity());
+        }
+        let pairs = args.take(args.items.len())?;
+
+        // validate everything before touching the entry
+        let mut vector = None;
+        for pair in pairs.chunks_exact(2) {
+            match pair[0].as_str() {
+                "VECTOR" => vector = Some(index.parse_vector(&pair[1])?),
+                "TEXT" if index.lexical_algorithm == Some(LexicalAlgorithm::Vector) => {
+                    parse::<SparseVector>(&pair[1], "sparse vector")?;
+                }
+                _ => {}
+            }
+        }
+
+        let entry = index.entries.entry(key.to_owned()).or_default();
+        if vector.is_some() {
+            entry.vector = vector;
+        }
+        let mut added = 0;
+        for pair in pairs.chunks_exact(2) {
+            if entry
+                .fields
+                .insert(pair[0].clone(), pair[1].clone())
+                .is_none()
+            {
+                added += 1;
+            }
+        }
+        Ok(Value::Int(added))
+    }
+
+    fn hgetall(&mut self, args: &mut Args) -> Reply {
+        let index = self.index(args.next()?)?;
+        let fields = index
+            .entries
+            .get(args.next()?)
+            .into_iter()
+            .flat_map(|entry| &entry.fields)
+            .flat_map(|(k, v)| [data(k), data(v)]);
+        Ok(Value::Bulk(fields.collect()))
+    }
+
+    fn hmget(&mut self, args: &mut Args) -> Reply {
+        let index = self.index(args.next()?)?;
+        let entry = index.entries.get(args.next()?);
+        let fields = args.take(args.items.len())?;
+        Ok(Value::Bulk(
+            fields
+                .iter()
+                .map(|f| match entry.and_then(|e| e.fields.get(f)) {
+                    Some(v) => data(v),
+                    None => Value::Nil,
+                })
+                .collect(),
+        ))
+    }
+
+    fn del(&mut self, args: &mut Args) -> Reply {
+        let index = self.index_mut(args.next()?)?;
+        let keys = args.take(args.items.len())?;
+        let mut deleted = 0;
+        for key in keys {
+            if index.entries.remove(key).is_some() {
+                deleted += 1;
+            }
+        }
+        index.deleted += deleted as u64;
+        Ok(Value::Int(deleted))
+    }
+
+    fn hdel(&mut self, args: &mut Args) -> Reply {
+        let index = self.index_mut(args.next()?)?;
+        let key = args.next()?;
+        let fields = args.take(args.items.len())?;
+        let entry = match index.entries.get_mut(key) {
+            Some(entry) => entry,
+            None => return Ok(Value::Int(0)),
+        };
+        let mut deleted = 0;
+        for field in fields {
+            if entry.fields.remove(field).is_some() {
+                deleted += 1;
+                if field == "VECTOR" {
+                    entry.vector = None;
+                }
+            }
+        }
+        if entry.fields.is_empty() {
+            index.entries.remove(key);
+            index.deleted += 1;
+        }
+        Ok(Value::Int(deleted))
+    }
+
+    fn scan(&mut self, args: &mut Args) -> Reply {
+        let index = self.index(args.next()?)?;
+        let _cursor: u64 = args.parse("cursor")?;
+        let (mut pattern, mut query, mut max_dist, mut filter) = (None, None, None, None);
+        while !args.is_empty() {
+            match args.next()?.to_ascii_uppercase().as_str() {
+                "MATCH" => pattern = Some(args.next()?),
+                "COUNT" => {
+                    args.parse::<usize>("count")?;
+                }
+                "VECTOR" => query = Some(index.parse_vector(args.next()?)?),
+                "MAX_DIST" => max_dist = Some(args.parse::<f32>("max_dist")?),
+                "FILTER" => filter = Some(Expr::parse(args.next()?)?),
+                _ => return Err("ERR syntax error".to_owned()),
+            }
+        }
+        let keys = index.entries.iter().filter_map(|(key, entry)| {
+            if let Some(p) = pattern {
+                if !glob_match(p.as_bytes(), key.as_bytes()) {
+                    return None;
+                }
+            }
+            if let (Some(query), Some(max_dist)) = (&query, max_dist) {
+                let vector = entry.vector.as_ref()?;
+                if index.distance(query, vector) > max_dist {
+                    return None;
+                }
+            }
+            match &filter {
+                Some(f) if !f.matches(&entry.fields) => None,
+                _ => Some(key),
+            }
+        });
+        Ok(scan_reply(keys))
+    }
+
+    fn knnsearch(&mut self, args: &mut Args, with_fields: bool) -> Reply {
+        let index = self.index(args.next()?)?;
+        let topk: usize = args.parse("topk")?;
+        let query = index.parse_vector(args.next()?)?;
+        let fields = if with_fields {
+            let count = args.parse("field count")?;
+            args.take(count)?
+        } else {
+            &[]
+        };
+        let options = SearchOptions::parse(args)?;
+        let hits = index.search(&query, topk, &options)?;
+        if !with_fields {
+            return Ok(hits_reply(hits));
+        }
+        Ok(Value::Bulk(
+            hits.into_iter()
+                .map(|(key, distance)| {
+                    let entry = &index.entries[&key];
+                    let mut items = vec![data(&key), data(distance.to_string())];
+                    for field in fields {
+                        if let Some(value) = entry.fields.get(field) {
+                            items.extend([data(field), data(value)]);
+                        }
+                    }
+                    Value::Bulk(items)
+                })
+                .collect(),
+        ))
+    }
+
+    fn mknnsearch(&mut self, args: &mut Args) -> Reply {
+        let index = self.index(args.next()?)?;
+        let topk: usize = args.parse("topk")?;
+        let count = args.parse("vector count")?;
+        let queries = args
+            .take(count)?
+            .iter()
+            .map(|v| index.parse_vector(v))
+            .collect::<Result<Vec<_>, _>>()?;
+        let options = SearchOptions::parse(args)?;
+        let results = queries
+            .iter()
+            .map(|query| Ok(hits_reply(index.search(query, topk, &options)?)))
+            .collect::<Result<_, String>>()?;
+        Ok(Value::Bulk(results))
+    }
+
+    fn mindex_knnsearch(&mut self, args: &mut Args) -> Reply {
+        let indices = self.indices_arg(args)?;
+        let topk: usize = args.parse("topk")?;
+        let query = args.next()?;
+        let options = SearchOptions::parse(args)?;
+        Ok(hits_reply(search_all(&indices, query, topk, &options)?))
+    }
+
+    fn mindex_mknnsearch(&mut self, args: &mut Args) -> Reply {
+        let indices = self.indices_arg(args)?;
+        let topk: usize = args.parse("topk")?;
+        let count = args.parse("vector count")?;
+        let queries = args.take(count)?;
+        let options = SearchOptions::parse(args)?;
+        let results = queries
+            .iter()
+            .map(|query| Ok(hits_reply(search_all(&indices, query, topk, &options)?)))
+            .collect::<Result<_, String>>()?;
+        Ok(Value::Bulk(results))
+    }
+
+    /// `index_count index_name1...`
+    fn indices_arg(&self, args: &mut Args) -> Result<Vec<&Index>, String> {
+        let count = args.parse("index count")?;
+        args.take(count)?
+            .iter()
+            .map(|name| self.index(name))
+            .collect()
+    }
+
+    fn get_distance(&mut self, args: &mut Args) -> Reply {
+        let index = self.index(args.next()?)?;
+        let query = index.parse_vector(args.next()?)?;
+        let count = args.parse("key count")?;
+        let keys = args.take(count)?;
+        let (mut topn, mut max_dist, mut filter) = (None, None, None);
+        while !args.is_empty() {
+            match args.next()?.to_ascii_uppercase().as_str() {
+                "TOPN" => topn = Some(args.parse::<usize>("topn")?),
+                "MAX_DIST" => max_dist = Some(args.parse::<f32>("max_dist")?),
+                "FILTER" => filter = Some(Expr::parse(args.next()?)?),
+                _ => return Err("ERR syntax error".to_owned()),
+            }
+        }
+        let mut hits: Vec<(String, f32)> = keys
+            .iter()
+            .filter_map(|key| {
+                let entry = index.entries.get(key)?;
+                if filter.as_ref().is_some_and(|f| !f.matches(&entry.fields)) {
+                    return None;
+                }
+                let distance = index.distance(&query, entry.vector.as_ref()?);
+                (distance <= max_dist.unwrap_or(f32::INFINITY)).then(|| (key.clone(), distance))
+            })
+            .collect();
+        sort_hits(&mut hits);
+        hits.truncate(topn.unwrap_or(usize::MAX));
+        Ok(hits_reply(hits))
+    }
+}
+
+impl Index {
+    fn set_param(&mut self, key: &str, value: &str) {
+        match self
+            .params
+            .iter_mut()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        {
+            Some((_, v)) => *v = value.to_owned(),
+            None => self.params.push((key.to_owned(), value.to_owned())),
+        }
+    }
+
+    fn parse_vector(&self, s: &str) -> Result<Vec<f32>, String> {
+        let vector: Vector = parse(s, "vector")?;
+        if vector.len() != self.dim {
+            return Err(format!(
+                "ERR dimension not match, expected {}, got {}",
+                self.dim,
+                vector.len()
+            ));
+        }
+        if self.data_type == DataType::Binary && vector.iter().any(|&x| x != 0.0 && x != 1.0) {
+            return Err(format!(
+                "ERR elements must be 0 or 1 for data type {}",
+                self.data_type
+            ));
+        }
+        Ok(vector.into_inner())
+    }
+
+    /// IP and COSINE report `1 - similarity`, L2 the squared distance.
+    fn distance(&self, a: &[f32], b: &[f32]) -> f32 {
+        let dot = |a: &[f32], b: &[f32]| -> f32 { a.iter().zip(b).map(|(x, y)| x * y).sum() };
+        match self.distance_type {
+            DistanceType::L2 => a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum(),
+            DistanceType::Ip => 1.0 - dot(a, b),
+            DistanceType::Cosine => {
+                let norm = (dot(a, a) * dot(b, b)).sqrt();
+                if norm == 0.0 {
+                    1.0
+                } else {
+                    1.0 - dot(a, b) / norm
+                }
+            }
+            DistanceType::Jaccard => {
+                let (mut both, mut either) = (0, 0);
+                for (x, y) in a.iter().zip(b) {
+                    let (x, y) = (*x != 0.0, *y != 0.0);
+                    both += (x && y) as u32;
+                    either += (x || y) as u32;
+                }
+                if either == 0 {
+                    0.0
+                } else {
+                    1.0 - both as f32 / either as f32
+                }
+            }
+        }
+    }
+
+    /// Brute force search, for FLAT and HNSW alike.
+    ///
+    /// With `TEXT` the distance is `hybrid_ratio * vector_distance +
+    /// (1 - hybrid_ratio) * (1 - text_score)`, where the text score is the
+    /// dot product of sparse vectors, or for BM25 the share of query terms
+    /// found in the text of the entry.
+    fn search(
+        &self,
+        query: &[f32],
+        topk: usize,
+        options: &SearchOptions,
+    ) -> Result<Vec<(String, f32)>, String> {
+        let text = match (&options.text, self.lexical_algorithm) {
+            (None, _) => None,
+            (Some(_), None) => {
+                return Err("ERR invalid parameter TEXT, index has no lexical_algorithm".to_owned())
+            }
+            (Some(text), Some(LexicalAlgorithm::Vector)) => {
+                Some(TextQuery::Sparse(parse(text, "sparse vector")?))
+            }
+            (Some(text), Some(LexicalAlgorithm::Bm25)) => Some(TextQuery::Terms(terms(text))),
+        };
+
+        let mut hits: Vec<(String, f32)> = self
+            .entries
+            .iter()
+            .filter_map(|(key, entry)| {
+                if options
+                    .filter
+                    .as_ref()
+                    .is_some_and(|f| !f.matches(&entry.fields))
+                {
+                    return None;
+                }
+                let mut distance = self.distance(query, entry.vector.as_ref()?);
+                if let Some(text) = &text {
+                    let score = text.score(entry.fields.get("TEXT").map(String::as_str));
+                    distance = options.hybrid_ratio * distance
+                        + (1.0 - options.hybrid_ratio) * (1.0 - score);
+                }
+                Some((key.clone(), distance))
+            })
+            .filter(|(_, d)| *d <= options.max_dist.unwrap_or(f32::INFINITY))
+            .collect();
+        sort_hits(&mut hits);
+        hits.truncate(topk);
+        Ok(hits)
+    }
+}
+
+/// `[filter] [param value]...` after the query vectors of the KNN commands.
+#[derive(Debug)]
+struct SearchOptions {
+    filter: Option<Expr>,
+    max_dist: Option<f32>,
+    text: Option<String>,
+    hybrid_ratio: f32,
+}
+
+impl SearchOptions {
+    fn parse(args: &mut Args) -> Result<SearchOptions, String> {
+        let mut options = SearchOptions {
+            filter: None,
+            max_dist: None,
+            text: None,
+            hybrid_ratio: 0.5,
+        };
+        // parameters come in pairs, so an odd argument out is the filter
+        if args.items.len() % 2 != 0 {
+            options.filter = Some(Expr::parse(args.next()?)?);
+        }
+        while !args.is_empty() {
+            let (key, value) = (args.next()?, args.next()?);
+            match key.to_ascii_lowercase().as_str() {
+                "ef_search" => {
+                    parse::<usize>(value, key)?;
+                }
+                "max_dist" => options.max_dist = Some(parse(value, key)?),
+                "text" => options.text = Some(value.to_owned()),
+                "hybrid_ratio" => options.hybrid_ratio = parse(value, key)?,
+                _ => return Err(format!("ERR invalid parameter: {}", key)),
+            }
+        }
+        Ok(options)
+    }
+}
+
+enum TextQuery {
+    Sparse(SparseVector),
+    Terms(HashSet<String>),
+}
+
+impl TextQuery {
+    fn score(&self, text: Option<&str>) -> f32 {
+        let text = match text {
+            Some(text) => text,
+            None => return 0.0,
+        };
+        match self {
+            TextQuery::Sparse(query) => {
+                let doc: SparseVector = text.parse().unwrap_or_default();
+                query
+                    .iter()
+                    .filter_map(|(i, x)| doc.iter().find(|&(j, _)| i == j).map(|(_, y)| x * y))
+                    .sum()
+            }
+            TextQuery::Terms(query) if query.is_empty() => 0.0,
+            TextQuery::Terms(query) => {
+                let doc = terms(text);
+                query.intersection(&doc).count() as f32 / query.len() as f32
+            }
+        }
+    }
+}
+
+fn terms(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn search_all(
+    indices: &[&Index],
+    query: &str,
+    topk: usize,
+    options: &SearchOptions,
+) -> Result<Vec<(String, f32)>, String> {
+    let mut hits = vec![];
+    for index in indices {
+        hits.extend(index.search(&index.parse_vector(query)?, topk, options)?);
+    }
+    sort_hits(&mut hits);
+    hits.truncate(topk);
+    Ok(hits)
+}
+
+fn sort_hits(hits: &mut [(String, f32)]) {
+    hits.sort_by(|a, b| a.1.total_cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+}
+
+fn hits_reply(hits: Vec<(String, f32)>) -> Value {
+    Value::Bulk(
+        hits.into_iter()
+            .flat_map(|(key, distance)| [data(key), data(distance.to_string())])
+            .collect(),
+    )
+}
+
+/// A single page with cursor 0, so that iteration ends after it.
+fn scan_reply<'a, I: Iterator<Item = &'a String>>(keys: I) -> Value {
+    Value::Bulk(vec![data("0"), Value::Bulk(keys.map(data).collect())])
+}
+
+fn data<S: AsRef<str>>(s: S) -> Value {
+    Value::Data(s.as_ref().as_bytes().to_vec())
+}
+
+fn index_not_found() -> String {
+    "ERR index not exists".to_owned()
+}
+
+fn parse<T: FromStr>(s: &str, what: &str) -> Result<T, String> {
+    s.parse()
+        .map_err(|_| format!("ERR invalid {}: {:?}", what, s))
+}
+
+/// Redis glob patterns with `*`, `?` and `\` escapes.
+fn glob_match(pattern: &[u8], s: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => s.is_empty(),
+        Some((b'*', rest)) => (0..=s.len()).any(|i| glob_match(rest, &s[i..])),
+        Some((b'?', rest)) => !s.is_empty() && glob_match(rest, &s[1..]),
+        Some((b'\\', [c, rest @ ..])) => s.first() == Some(c) && glob_match(rest, &s[1..]),
+        Some((c, rest)) => s.first() == Some(c) && glob_match(rest, &s[1..]),
+    }
+}
+
+/// The arguments of a command, consumed front to back.
+struct Args<'a> {
+    command: &'a str,
+    items: &'a [String],
+}
+
+impl<'a> Args<'a> {
+    fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    fn next(&mut self) -> Result<&'a str, String> {
+        Ok(&self.take(1)?[0])
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [String], String> {
+        if n > self.items.len() {
+            return Err(self.wrong_arity());
+        }
+        let (taken, rest) = self.items.split_at(n);
+        self.items = rest;
+        Ok(taken)
+    }
+
+    fn parse<T: FromStr>(&mut self, what: &str) -> Result<T, String> {
+        parse(self.next()?, what)
+    }
+
+    fn wrong_arity(&self) -> String {
+        format!(
+            "ERR wrong number of arguments for '{}' command",
+            self.command.to_ascii_lowercase()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(engine: &mut Engine, command: &str) -> Reply {
+        let args: Vec<Vec<u8>> = command.split(' ').map(|a| a.as_bytes().to_vec()).collect();
+        engine.execute(&args)
+    }
+
+    fn keys_and_distances(reply: Reply) -> Vec<(String, f32)> {
+        redis::from_redis_value(&reply.unwrap()).unwrap()
+    }
+
+    #[test]
+    fn metrics() {
+        let mut engine = Engine::default();
+        for (name, metric) in [("l2", "L2"), ("ip", "IP"), ("cos", "COSINE")] {
+            let created = run(
+                &mut engine,
+                &format!("TVS.CREATEINDEX {} 2 HNSW {}", name, metric),
+            );
+            assert_eq!(created, Ok(Value::Okay));
+            run(&mut engine, &format!("TVS.HSET {} a VECTOR [1,0]", name)).unwrap();
+            run(&mut engine, &format!("TVS.HSET {} b VECTOR [0,2]", name)).unwrap();
+        }
+
+        let l2 = keys_and_distances(run(&mut engine, "TVS.KNNSEARCH l2 2 [1,1]"));
+        assert_eq!(l2, [("a".to_owned(), 1.0), ("b".to_owned(), 2.0)]);
+        let ip = keys_and_distances(run(&mut engine, "TVS.KNNSEARCH ip 2 [1,1]"));
+        assert_eq!(ip, [("b".to_owned(), -1.0), ("a".to_owned(), 0.0)]);
+        let cos = keys_and_distances(run(&mut engine, "TVS.KNNSEARCH cos 1 [0,3]"));
+        assert_eq!(cos, [("b".to_owned(), 0.0)]);
+
+        let created = run(
+            &mut engine,
+            "TVS.CREATEINDEX bin 4 FLAT JACCARD data_type BINARY",
+        );
+        assert_eq!(created, Ok(Value::Okay));
+        run(&mut engine, "TVS.HSET bin a VECTOR [1,1,0,0]").unwrap();
+        let jaccard = keys_and_distances(run(&mut engine, "TVS.KNNSEARCH bin 1 [1,0,0,0]"));
+        assert_eq!(jaccard, [("a".to_owned(), 0.5)]);
+        assert!(run(&mut engine, "TVS.HSET bin b VECTOR [2,0,0,0]").is_err());
+
+        assert_eq!(
+            run(&mut engine, "TVS.HSET l2 c VECTOR [1,2,3]"),
+            Err("ERR dimension not match, expected 2, got 3".to_owned())
+        );
+        assert_eq!(
+            run(&mut engine, "TVS.HSET missing c VECTOR [1,2]"),
+            Err("ERR index not exists".to_owned())
+        );
+        assert!(run(&mut engine, "TVS.KNNSEARCH l2 2").is_err());
+        assert!(run(&mut engine, "TVS.FOO").is_err());
+    }
+}
@@ -0,0 +1,257 @@
+use crate::{CompareOp, FilterValue};
+use std::collections::BTreeMap;
+
+/// A parsed FILTER expression, evaluated against the attributes of an entry.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Expr {
+    Const(bool),
+    Compare {
+        field: String,
+        op: CompareOp,
+        value: FilterValue,
+    },
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+}
+
+impl Expr {
+    /// Parses the syntax rendered by `Filter`: comparisons of an attribute
+    /// with a constant, combined with `&&`, `||`, `!` and parentheses.
+    pub(crate) fn parse(s: &str) -> Result<Expr, String> {
+        let invalid = || format!("ERR invalid filter: {}", s);
+        let mut parser = Parser {
+            tokens: tokenize(s).ok_or_else(invalid)?,
+            pos: 0,
+        };
+        match parser.or() {
+            Some(expr) if parser.pos == parser.tokens.len() => Ok(expr),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Comparisons on missing attributes, or on attributes that do not parse
+    /// as the type of the constant, are false.
+    pub(crate) fn matches(&self, fields: &BTreeMap<String, String>) -> bool {
+        match self {
+            Expr::Const(b) => *b,
+            Expr::Compare { field, op, value } => fields
+                .get(field)
+                .is_some_and(|attr| compare(attr, *op, value)),
+            Expr::And(l, r) => l.matches(fields) && r.matches(fields),
+            Expr::Or(l, r) => l.matches(fields) || r.matches(fields),
+            Expr::Not(e) => !e.matches(fields),
+        }
+    }
+}
+
+fn compare(attr: &str, op: CompareOp, value: &FilterValue) -> bool {
+    let ordering = match value {
+        FilterValue::Int(v) => attr
+            .parse::<f64>()
+            .ok()
+            .and_then(|a| a.partial_cmp(&(*v as f64))),
+        FilterValue::UInt(v) => attr
+            .parse::<f64>()
+            .ok()
+            .and_then(|a| a.partial_cmp(&(*v as f64))),
+        FilterValue::Float(v) => attr.parse::<f64>().ok().and_then(|a| a.partial_cmp(v)),
+        FilterValue::Bool(v) => attr.parse::<bool>().ok().map(|a| a.cmp(v)),
+        FilterValue::Str(v) => Some(attr.cmp(v.as_str())),
+    };
+    ordering.is_some_and(|o| match op {
+        CompareOp::Eq => o.is_eq(),
+        CompareOp::Ne => o.is_ne(),
+        CompareOp::Gt => o.is_gt(),
+        CompareOp::Ge => o.is_ge(),
+        CompareOp::Lt => o.is_lt(),
+        CompareOp::Le => o.is_le(),
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Value(FilterValue),
+    Op(CompareOp),
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+}
+
+fn tokenize(s: &str) -> Option<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut chars = s.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '&' => chars.next_if(|&(_, d)| d == '&').map(|_| Token::And)?,
+            '|' => chars.next_if(|&(_, d)| d == '|').map(|_| Token::Or)?,
+            '=' | '!' | '<' | '>' => {
+                let eq = chars.next_if(|&(_, d)| d == '=').is_some();
+                match (c, eq) {
+                    ('=', true) => Token::Op(CompareOp::Eq),
+                    ('!', true) => Token::Op(CompareOp::Ne),
+                    ('!', false) => Token::Not,
+                    ('<', true) => Token::Op(CompareOp::Le),
+                    ('<', false) => Token::Op(CompareOp::Lt),
+                    ('>', true) => Token::Op(CompareOp::Ge),
+                    ('>', false) => Token::Op(CompareOp::Gt),
+                    _ => return None,
+                }
+            }
+            '"' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next()?.1 {
+                        '\\' => value.push(match chars.next()?.1 {
+                            'n' => '\n',
+                            'r' => '\r',
+                            't' => '\t',
+                            c => c,
+                        }),
+                        '"' => break,
+                        c => value.push(c),
+                    }
+                }
+                Token::Value(FilterValue::Str(value))
+            }
+            c if c.is_ascii_digit() || c == '-' || c == '.' => {
+                while chars
+                    .next_if(|&(_, d)| d.is_ascii_alphanumeric() || ".+-".contains(d))
+                    .is_some()
+                {}
+                let end = chars.peek().map_or(s.len(), |&(i, _)| i);
+                let text = &s[start..end];
+                if let Ok(v) = text.parse() {
+                    Token::Value(FilterValue::Int(v))
+                } else if let Ok(v) = text.parse() {
+                    Token::Value(FilterValue::UInt(v))
+                } else {
+                    let v: f64 = text.parse().ok()?;
+                    Token::Value(FilterValue::Float(v.is_finite().then_some(v)?))
+                }
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                while chars
+                    .next_if(|&(_, d)| d.is_alphanumeric() || d == '_' || d == '.')
+                    .is_some()
+                {}
+                let end = chars.peek().map_or(s.len(), |&(i, _)| i);
+                match &s[start..end] {
+                    "true" => Token::Value(FilterValue::Bool(true)),
+                    "false" => Token::Value(FilterValue::Bool(false)),
+                    word => Token::Ident(word.to_owned()),
+                }
+            }
+            _ => return None,
+        };
+        tokens.push(token);
+    }
+    Some(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned()?;
+        self.pos += 1;
+        Some(token)
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        let matched = self.tokens.get(self.pos) == Some(token);
+        if matched {
+            self.pos += 1;
+        }
+        matched
+    }
+
+    fn or(&mut self) -> Option<Expr> {
+        let mut expr = self.and()?;
+        while self.eat(&Token::Or) {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Some(expr)
+    }
+
+    fn and(&mut self) -> Option<Expr> {
+        let mut expr = self.unary()?;
+        while self.eat(&Token::And) {
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Some(expr)
+    }
+
+    fn unary(&mut self) -> Option<Expr> {
+        match self.advance()? {
+            Token::Not => Some(Expr::Not(Box::new(self.unary()?))),
+            Token::LParen => {
+                let expr = self.or()?;
+                self.eat(&Token::RParen).then_some(expr)
+            }
+            Token::Value(FilterValue::Bool(b)) => Some(Expr::Const(b)),
+            Token::Ident(field) => {
+                let Token::Op(op) = self.advance()? else {
+                    return None;
+                };
+                let Token::Value(value) = self.advance()? else {
+                    return None;
+                };
+                Some(Expr::Compare { field, op, value })
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field;
+
+    #[test]
+    fn parse_and_match() {
+        let fields: BTreeMap<String, String> =
+            [("price", "15"), ("name", r#"say "hi""#), ("sold", "false")]
+                .into_iter()
+                .map(|(k, v)| (k.to_owned(), v.to_owned()))
+                .collect();
+        let matches = |s: &str| Expr::parse(s).unwrap().matches(&fields);
+
+        assert!(matches("price > 10"));
+        assert!(matches("price <= 15.0 && price >= -1e3"));
+        assert!(!matches("price > 10 && sold == false && missing == 1"));
+        assert!(matches("missing == 1 || sold != true"));
+        assert!(matches("!(missing == 1)"));
+        assert!(!matches("false"));
+        assert!(matches(r#"name == "say \"hi\"""#));
+        assert!(!matches("name > 10"));
+
+        // round trip of rendered filters
+        let filter = (field("price").gt(10) | field("tag").in_set(["a", "b"]))
+            & !field("tag").in_set(Vec::<i32>::new());
+        assert!(matches(&filter.to_string()));
+        let filter = filter & !field("name").eq("say \"hi\"");
+        assert!(!matches(&filter.to_string()));
+
+        for s in [
+            "",
+            "price >",
+            "price = 1",
+            "(price > 1",
+            "price > 1 &",
+            "\"x",
+        ] {
+            assert!(Expr::parse(s).is_err(), "{:?}", s);
+        }
+    }
+}
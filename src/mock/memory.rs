@@ -0,0 +1,223 @@
+use super::engine::{Engine, Reply};
+use super::read_command;
+use redis::{ConnectionLike, RedisResult, Value};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// A TairVector backend in memory, used in place of a connection.
+///
+/// Implements `redis::ConnectionLike`, so `TairVectorCommands`, pipelines,
+/// `ValidatedIndex` and everything else taking a sync connection work on it
+/// without a server. Commands are executed by the same code as
+/// `MockServer`: brute force search with the distance metrics and FILTER
+/// semantics of TairVector. Clones share their indices, like connections to
+/// one server.
+///
+/// ```
+/// use tair_vector_rs::{InMemoryTairVector, KnnResult, TairVectorCommands};
+///
+/// let mut conn = InMemoryTairVector::new();
+/// let _: bool = conn.tvs_create_index("idx", 2, "FLAT", "L2").unwrap();
+/// let _: usize = conn.tvs_hset_vector("idx", "k1", [1.0f32, 2.0]).unwrap();
+/// let result: KnnResult = conn.tvs_knnsearch("idx", 1, [1.0f32, 2.0]).unwrap();
+/// assert_eq!(result[0].key, "k1");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryTairVector {
+    engine: Arc<Mutex<Engine>>,
+}
+
+impl InMemoryTairVector {
+    pub fn new() -> Self {
+        InMemoryTairVector::default()
+    }
+
+    /// Executes every command in `packed`, returning all replies or the
+    /// first error, as a pipeline on a connection does.
+    fn execute_packed(&mut self, mut packed: &[u8]) -> RedisResult<Vec<Value>> {
+        let mut engine = self.engine.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut replies = vec![];
+        let mut first_err = None;
+        while let Some(args) = read_command(&mut packed)? {
+            match into_result(engine.execute(&args)) {
+                Ok(value) => replies.push(value),
+                Err(e) => {
+                    first_err.get_or_insert(e);
+                }
+            }
+        }
+        first_err.map_or(Ok(replies), Err)
+    }
+}
+
+/// Turns an error line into the `RedisError` a connection would return.
+fn into_result(reply: Reply) -> RedisResult<Value> {
+    match reply {
+        Ok(value) => Ok(value),
+        Err(e) => redis::parse_redis_value(format!("-{}\r\n", e).as_bytes()),
+    }
+}
+
+impl ConnectionLike for InMemoryTairVector {
+    fn req_packed_command(&mut self, cmd: &[u8]) -> RedisResult<Value> {
+        let mut replies = self.execute_packed(cmd)?;
+        Ok(replies.pop().unwrap_or(Value::Nil))
+    }
+
+    fn req_packed_commands(
+        &mut self,
+        cmd: &[u8],
+        offset: usize,
+        count: usize,
+    ) -> RedisResult<Vec<Value>> {
+        let replies = self.execute_packed(cmd)?;
+        Ok(replies.into_iter().skip(offset).take(count).collect())
+    }
+
+    fn get_db(&self) -> i64 {
+        0
+    }
+
+    fn check_connection(&mut self) -> bool {
+        true
+    }
+
+    fn is_open(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        field, DistanceType, IndexSpec, IndexType, KnnQuery, KnnResult, TairVectorCommands,
+        TairVectorError, TairVectorPipeline, ValidatedIndex, Vector,
+    };
+    use redis::Iter;
+
+    /// Hits with distances rounded, so that results of a real server compare
+    /// equal despite floating point differences.
+    fn hits(result: &KnnResult) -> Vec<String> {
+        result
+            .iter()
+            .map(|hit| {
+                let mut attributes: Vec<_> = hit.attributes.iter().collect();
+                attributes.sort();
+                format!("{} {:.4} {:?}", hit.key, hit.distance, attributes)
+            })
+            .collect()
+    }
+
+    /// Runs the same commands on `conn`, returning everything read back.
+    fn scenario<C: ConnectionLike>(conn: &mut C, index_name: &str) -> Vec<String> {
+        // cleanup
+        conn.tvs_del_index::<_, usize>(index_name).unwrap();
+
+        let spec = IndexSpec::new(index_name, 3, IndexType::Hnsw, DistanceType::Cosine);
+        let created: bool = conn.tvs_create_index_spec(&spec).unwrap();
+        assert!(created);
+
+        let mut out = vec![];
+        for (i, tag) in ["red", "green", "blue", "red"].into_iter().enumerate() {
+            let vector = Vector::new(vec![1.0, i as f32, (i * i) as f32 / 4.0])
+                .unwrap()
+                .to_string();
+            let price = (i * 10).to_string();
+            let _: usize = conn
+                .tvs_hset_multi(
+                    index_name,
+                    format!("item:{}", i),
+                    &[("VECTOR", vector.as_str()), ("tag", tag), ("price", &price)],
+                )
+                .unwrap();
+        }
+
+        let query = [1.0f32, 1.5, 0.5];
+        let result: KnnResult = conn.tvs_knnsearch(index_name, 3, query).unwrap();
+        out.extend(hits(&result));
+
+        let knn = KnnQuery::new(4)
+            .filter(field("tag").eq("red") | field("price").gt(15))
+            .max_dist(0.5)
+            .output_fields(&["tag"]);
+        let result: KnnResult = conn.tvs_knnsearch_query(index_name, query, &knn).unwrap();
+        out.extend(hits(&result));
+
+        let iter: Iter<String> = conn
+            .tvs_scan_full(
+                index_name,
+                Some("item:*"),
+                Some((query, 0.1)),
+                Some(!field("tag").eq("green")),
+            )
+            .unwrap();
+        let mut keys: Vec<_> = iter.collect();
+        keys.sort();
+        out.push(format!("{:?}", keys));
+
+        let got: Vec<Option<String>> = conn
+            .tvs_hmget(index_name, "item:2", &["tag", "missing"])
+            .unwrap();
+        out.push(format!("{:?}", got));
+
+        conn.tvs_del_index::<_, usize>(index_name).unwrap();
+        out
+    }
+
+    /// Distances worked out by hand, for the in-memory engine, `MockServer`
+    /// behind RESP, or Tair when `TAIR_URL` is set.
+    const EXPECTED: [&str; 8] = [
+        "item:2 0.0180 []",
+        "item:1 0.0230 []",
+        "item:3 0.0876 []",
+        "item:2 0.0180 [(\"tag\", \"blue\")]",
+        "item:3 0.0876 [(\"tag\", \"red\")]",
+        "item:0 0.4655 [(\"tag\", \"red\")]",
+        "[\"item:2\", \"item:3\"]",
+        "[Some(\"blue\"), None]",
+    ];
+
+    #[test]
+    fn resp_round_trip() {
+        let client = redis::Client::open(crate::mock::test_url()).unwrap();
+        let mut server = client.get_connection().unwrap();
+        assert_eq!(scenario(&mut server, "test-in-memory"), EXPECTED);
+        assert_eq!(
+            scenario(&mut InMemoryTairVector::new(), "test-in-memory"),
+            EXPECTED
+        );
+    }
+
+    #[test]
+    fn in_memory() {
+        let mut conn = InMemoryTairVector::new();
+        let index_name = "idx";
+
+        let err = conn
+            .tvs_hset_vector::<_, _, _, usize>(index_name, "k1", [1.0f32, 2.0])
+            .unwrap_err();
+        assert!(matches!(
+            TairVectorError::from(err),
+            TairVectorError::IndexNotFound(_)
+        ));
+
+        let spec = IndexSpec::new(index_name, 2, IndexType::Flat, DistanceType::L2);
+        let (created, count, result): (bool, usize, KnnResult) = redis::pipe()
+            .tvs_create_index_spec(&spec)
+            .tvs_hset_vector(index_name, "k1", [1.0f32, 2.0])
+            .tvs_knnsearch(index_name, 1, [1.0f32, 2.0])
+            .query(&mut conn)
+            .unwrap();
+        assert!(created);
+        assert_eq!(count, 1);
+        assert_eq!(result[0].key, "k1");
+
+        // clones share the indices
+        let mut other = conn.clone();
+        let mut index = ValidatedIndex::open(&mut other, index_name).unwrap();
+        assert_eq!(index.dimension(), 2);
+        let _: usize = index.hset_vector(&mut other, "k2", [3.0f32, 4.0]).unwrap();
+        let keys: Vec<String> = conn.tvs_scan(index_name).unwrap().collect();
+        assert_eq!(keys, ["k1", "k2"]);
+    }
+}
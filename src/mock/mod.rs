@@ -0,0 +1,224 @@
+mod engine;
+mod filter;
+mod memory;
+
+use self::engine::{Engine, Reply};
+#[cfg(feature = "mock")]
+pub use self::memory::InMemoryTairVector;
+use redis::Value;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+
+/// An in-process TairVector server for tests, listening on localhost.
+///
+/// Implements the TVS.* commands sent by this crate on an in-memory store.
+/// Searches are brute force for FLAT and HNSW indices alike, so results are
+/// exact; index parameters such as `ef_construct` are reported back by
+/// TVS.GETINDEX but otherwise ignored. The server stops accepting
+/// connections when dropped. `InMemoryTairVector` runs the same commands
+/// without a socket.
+///
+/// ```
+/// use tair_vector_rs::{MockServer, TairVectorCommands};
+///
+/// let server = MockServer::start().unwrap();
+/// let client = redis::Client::open(server.url()).unwrap();
+/// let mut conn = client.get_connection().unwrap();
+/// let created: bool = conn.tvs_create_index("idx", 2, "FLAT", "L2").unwrap();
+/// assert!(created);
+/// ```
+#[derive(Debug)]
+pub struct MockServer {
+    addr: SocketAddr,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl MockServer {
+    /// Binds to a free port on 127.0.0.1 and serves each connection on its
+    /// own thread.
+    pub fn start() -> io::Result<MockServer> {
+        MockServer::start_with(Arc::new(Mutex::new(Engine::default())))
+    }
+
+    /// Starts `n` servers that report themselves as the masters of a
+    /// cluster, with the slots split evenly between them. Each server keeps
+    /// only what is sent to it, so tests can tell where a command was routed.
+    #[cfg(all(test, feature = "cluster"))]
+    pub(crate) fn start_cluster(n: usize) -> io::Result<Vec<MockServer>> {
+        let engines: Vec<_> = (0..n)
+            .map(|_| Arc::new(Mutex::new(Engine::default())))
+            .collect();
+        let servers = engines
+            .iter()
+            .map(|engine| MockServer::start_with(engine.clone()))
+            .collect::<io::Result<Vec<_>>>()?;
+        let slots = Value::Bulk(
+            servers
+                .iter()
+                .enumerate()
+                .map(|(i, server)| {
+                    Value::Bulk(vec![
+                        Value::Int((i * 16384 / n) as i64),
+                        Value::Int(((i + 1) * 16384 / n - 1) as i64),
+                        Value::Bulk(vec![
+                            Value::Data(b"127.0.0.1".to_vec()),
+                            Value::Int(server.addr.port().into()),
+                            Value::Data(format!("node-{}", i).into_bytes()),
+                        ]),
+                    ])
+                })
+                .collect(),
+        );
+        for engine in &engines {
+            engine.lock().unwrap().cluster_slots = Some(slots.clone());
+        }
+        Ok(servers)
+    }
+
+    fn start_with(engine: Arc<Mutex<Engine>>) -> io::Result<MockServer> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let stop = shutdown.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stop.load(Ordering::SeqCst) {
+                    break;
+                }
+                if let Ok(stream) = stream {
+                    let engine = engine.clone();
+                    thread::spawn(move || serve(stream, &engine));
+                }
+            }
+        });
+        Ok(MockServer { addr, shutdown })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// `redis://127.0.0.1:port/`, for `redis::Client::open`.
+    pub fn url(&self) -> String {
+        format!("redis://{}/", self.addr())
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        // wake up the accept loop
+        let _ = TcpStream::connect(self.addr);
+    }
+}
+
+/// `TAIR_URL` if set, otherwise a mock server shared by all tests.
+#[cfg(test)]
+pub(crate) fn test_url() -> String {
+    static SERVER: std::sync::OnceLock<MockServer> = std::sync::OnceLock::new();
+    match std::env::var("TAIR_URL") {
+        Ok(url) => url,
+        Err(_) => SERVER.get_or_init(|| MockServer::start().unwrap()).url(),
+    }
+}
+
+fn serve(stream: TcpStream, engine: &Mutex<Engine>) -> io::Result<()> {
+    stream.set_nodelay(true)?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+    while let Some(args) = read_command(&mut reader)? {
+        let reply = engine
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .execute(&args);
+        let mut out = vec![];
+        write_reply(&mut out, &reply);
+        writer.write_all(&out)?;
+    }
+    Ok(())
+}
+
+/// Reads a command sent as an array of bulk strings, or an inline command.
+/// Returns `None` once the client disconnects.
+fn read_command<R: BufRead>(reader: &mut R) -> io::Result<Option<Vec<Vec<u8>>>> {
+    let mut line = vec![];
+    if reader.read_until(b'\n', &mut line)? == 0 {
+        return Ok(None);
+    }
+    let count = match trim_crlf(&line).strip_prefix(b"*") {
+        Some(count) => parse_len(count)?,
+        None => {
+            let args = trim_crlf(&line)
+                .split(u8::is_ascii_whitespace)
+                .filter(|a| !a.is_empty())
+                .map(<[u8]>::to_vec);
+            return Ok(Some(args.collect()));
+        }
+    };
+
+    let mut args = Vec::with_capacity(count);
+    for _ in 0..count {
+        let mut header = vec![];
+        reader.read_until(b'\n', &mut header)?;
+        let len = trim_crlf(&header)
+            .strip_prefix(b"$")
+            .ok_or_else(|| protocol_error("expected a bulk string"))?;
+        let len = parse_len(len)?;
+        let mut arg = vec![0; len + 2];
+        reader.read_exact(&mut arg)?;
+        arg.truncate(len);
+        args.push(arg);
+    }
+    Ok(Some(args))
+}
+
+fn trim_crlf(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+fn parse_len(s: &[u8]) -> io::Result<usize> {
+    std::str::from_utf8(s)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| protocol_error("invalid length"))
+}
+
+fn protocol_error(detail: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, detail)
+}
+
+fn write_reply(out: &mut Vec<u8>, reply: &Reply) {
+    match reply {
+        Ok(value) => write_value(out, value),
+        Err(e) => {
+            out.push(b'-');
+            out.extend_from_slice(e.as_bytes());
+            out.extend_from_slice(b"\r\n");
+        }
+    }
+}
+
+fn write_value(out: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::Nil => out.extend_from_slice(b"$-1\r\n"),
+        Value::Int(n) => out.extend_from_slice(format!(":{}\r\n", n).as_bytes()),
+        Value::Data(data) => {
+            out.extend_from_slice(format!("${}\r\n", data.len()).as_bytes());
+            out.extend_from_slice(data);
+            out.extend_from_slice(b"\r\n");
+        }
+        Value::Bulk(items) => {
+            out.extend_from_slice(format!("*{}\r\n", items.len()).as_bytes());
+            for item in items {
+                write_value(out, item);
+            }
+        }
+        Value::Status(s) => out.extend_from_slice(format!("+{}\r\n", s).as_bytes()),
+        Value::Okay => out.extend_from_slice(b"+OK\r\n"),
+    }
+}
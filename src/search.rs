@@ -0,0 +1,477 @@
+use crate::{DistanceType, Filter};
+use redis::{ErrorKind, FromRedisValue, RedisError, RedisResult, RedisWrite, ToRedisArgs, Value};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::ops::Deref;
+
+/// Options of a KNN search, see `tvs_knnsearch_query`.
+///
+/// ```
+/// use tair_vector_rs::{field, KnnQuery};
+///
+/// let query = KnnQuery::new(10)
+///     .ef_search(200)
+///     .filter(field("price").lt(100))
+///     .max_dist(0.8)
+///     .output_fields(&["name", "price"]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct KnnQuery {
+    pub topk: usize,
+    pub ef_search: Option<usize>,
+    /// Checked when the command is built.
+    pub filter: Option<Filter>,
+    /// A FILTER expression sent as is, in place of `filter`.
+    pub filter_raw: Option<String>,
+    pub max_dist: Option<f32>,
+    /// Attributes returned with each hit. Queries with output fields are
+    /// sent as TVS.KNNSEARCHFIELD.
+    pub output_fields: Vec<String>,
+}
+
+impl KnnQuery {
+    pub fn new(topk: usize) -> Self {
+        KnnQuery {
+            topk,
+            ef_search: None,
+            filter: None,
+            filter_raw: None,
+            max_dist: None,
+            output_fields: vec![],
+        }
+    }
+
+    /// HNSW only.
+    pub fn ef_search(mut self, ef_search: usize) -> Self {
+        self.ef_search = Some(ef_search);
+        self
+    }
+
+    pub fn filter<F: Into<Filter>>(mut self, filter: F) -> Self {
+        self.filter = Some(filter.into());
+        self.filter_raw = None;
+        self
+    }
+
+    /// A FILTER expression that is sent without any validation.
+    pub fn filter_raw<F: ToString>(mut self, filter: F) -> Self {
+        self.filter_raw = Some(filter.to_string());
+        self.filter = None;
+        self
+    }
+
+    pub fn max_dist(mut self, max_dist: f32) -> Self {
+        self.max_dist = Some(max_dist);
+        self
+    }
+
+    pub fn output_fields<F: ToString>(mut self, fields: &[F]) -> Self {
+        self.output_fields = fields.iter().map(ToString::to_string).collect();
+        self
+    }
+
+    /// Rejects a filter that cannot be rendered.
+    pub(crate) fn check(&self) -> RedisResult<()> {
+        match (&self.filter_raw, &self.filter) {
+            (None, Some(filter)) => filter.check(),
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn command_name(&self) -> &'static str {
+        if self.output_fields.is_empty() {
+            "TVS.KNNSEARCH"
+        } else {
+            "TVS.KNNSEARCHFIELD"
+        }
+    }
+
+    /// The arguments after the query vector.
+    pub(crate) fn tail_args(&self) -> KnnQueryTail<'_> {
+        KnnQueryTail(self)
+    }
+}
+
+pub(crate) struct KnnQueryTail<'a>(&'a KnnQuery);
+
+impl ToRedisArgs for KnnQueryTail<'_> {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        let query = self.0;
+        if !query.output_fields.is_empty() {
+            query.output_fields.len().write_redis_args(out);
+            query.output_fields.write_redis_args(out);
+        }
+        match &query.filter_raw {
+            Some(raw) => raw.write_redis_args(out),
+            None => query.filter.write_redis_args(out),
+        }
+        if let Some(ef_search) = query.ef_search {
+            out.write_arg(b"ef_search");
+            ef_search.write_redis_args(out);
+        }
+        if let Some(max_dist) = query.max_dist {
+            out.write_arg(b"MAX_DIST");
+            max_dist.write_redis_args(out);
+        }
+    }
+}
+
+/// A single result of a KNN search.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub key: String,
+    pub distance: f32,
+    /// Attributes returned along with the hit, empty unless output fields
+    /// were requested.
+    pub attributes: HashMap<String, String>,
+}
+
+impl SearchHit {
+    /// Converts the distance into a similarity score where larger is closer.
+    ///
+    /// For IP, COSINE and JACCARD the server reports `1 - similarity`. L2 has
+    /// no bounded similarity, so `None` is returned.
+    pub fn similarity(&self, distance_type: DistanceType) -> Option<f32> {
+        match distance_type {
+            DistanceType::Ip | DistanceType::Cosine | DistanceType::Jaccard => {
+                Some(1.0 - self.distance)
+            }
+            DistanceType::L2 => None,
+        }
+    }
+
+    fn cmp_by_distance(&self, other: &Self) -> Ordering {
+        self.distance
+            .total_cmp(&other.distance)
+            .then_with(|| self.key.cmp(&other.key))
+    }
+}
+
+impl FromRedisValue for SearchHit {
+    /// Decodes a nested hit: `[key, distance]` followed by the attributes,
+    /// either inline or as a nested field/value array.
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        let items = match v {
+            Value::Bulk(items) if items.len() >= 2 => items,
+            _ => return Err(invalid_reply(v)),
+        };
+        let attributes = match &items[2..] {
+            [] => HashMap::new(),
+            [nested @ Value::Bulk(_)] => HashMap::from_redis_value(nested)?,
+            inline => HashMap::from_redis_value(&Value::Bulk(inline.to_vec()))?,
+        };
+        Ok(SearchHit {
+            key: String::from_redis_value(&items[0])?,
+            distance: f32::from_redis_value(&items[1])?,
+            attributes,
+        })
+    }
+
+    /// Decodes both the plain `[key1, distance1, key2, distance2, ...]` reply
+    /// and a list of nested hits.
+    fn from_redis_values(items: &[Value]) -> RedisResult<Vec<Self>> {
+        if items.iter().all(|item| matches!(item, Value::Bulk(_))) {
+            return items.iter().map(SearchHit::from_redis_value).collect();
+        }
+        if items.len() % 2 != 0 {
+            return Err(invalid_reply(&Value::Bulk(items.to_vec())));
+        }
+        items
+            .chunks_exact(2)
+            .map(|pair| {
+                Ok(SearchHit {
+                    key: String::from_redis_value(&pair[0])?,
+                    distance: f32::from_redis_value(&pair[1])?,
+                    attributes: HashMap::new(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// The hits of one KNN query, closest first as returned by the server.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KnnResult(pub Vec<SearchHit>);
+
+impl KnnResult {
+    /// Sorts hits by ascending distance, breaking ties by key.
+    pub fn sort(&mut self) {
+        self.0.sort_by(SearchHit::cmp_by_distance);
+    }
+
+    /// Keeps at most `topk` hits.
+    pub fn truncate(&mut self, topk: usize) {
+        self.0.truncate(topk);
+    }
+
+    /// Merges the results of several queries, e.g. over several indices, into
+    /// a single top-k.
+    pub fn merge<I: IntoIterator<Item = KnnResult>>(results: I, topk: usize) -> KnnResult {
+        let mut merged = KnnResult(results.into_iter().flat_map(|r| r.0).collect());
+        merged.sort();
+        merged.truncate(topk);
+        merged
+    }
+
+    /// `(key, similarity)` pairs, see `SearchHit::similarity`.
+    pub fn similarities(&self, distance_type: DistanceType) -> Option<Vec<(String, f32)>> {
+        self.0
+            .iter()
+            .map(|hit| Some((hit.key.clone(), hit.similarity(distance_type)?)))
+            .collect()
+    }
+
+    pub fn into_inner(self) -> Vec<SearchHit> {
+        self.0
+    }
+}
+
+impl Deref for KnnResult {
+    type Target = [SearchHit];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl IntoIterator for KnnResult {
+    type Item = SearchHit;
+    type IntoIter = std::vec::IntoIter<SearchHit>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl FromRedisValue for KnnResult {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        Ok(KnnResult(Vec::from_redis_value(v)?))
+    }
+}
+
+fn invalid_reply(v: &Value) -> RedisError {
+    RedisError::from((
+        ErrorKind::TypeError,
+        "Response was of incompatible type",
+        format!("invalid search hit (response was {:?})", v),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{field, DistanceType, IndexSpec, IndexType, TairVectorCommands};
+
+    fn data(s: &str) -> Value {
+        Value::Data(s.as_bytes().to_vec())
+    }
+
+    fn hit(key: &str, distance: f32) -> SearchHit {
+        SearchHit {
+            key: key.to_owned(),
+            distance,
+            attributes: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn decode_plain() {
+        let reply = Value::Bulk(vec![data("k1"), data("0.5"), data("k2"), data("1.25")]);
+        let result = KnnResult::from_redis_value(&reply).unwrap();
+        assert_eq!(result.0, vec![hit("k1", 0.5), hit("k2", 1.25)]);
+
+        let empty = KnnResult::from_redis_value(&Value::Bulk(vec![])).unwrap();
+        assert!(empty.is_empty());
+
+        let odd = Value::Bulk(vec![data("k1"), data("0.5"), data("k2")]);
+        assert!(KnnResult::from_redis_value(&odd).is_err());
+    }
+
+    #[test]
+    fn decode_with_attributes() {
+        let reply = Value::Bulk(vec![
+            Value::Bulk(vec![
+                data("k1"),
+                data("0.5"),
+                Value::Bulk(vec![data("name"), data("a"), data("price"), data("10")]),
+            ]),
+            Value::Bulk(vec![data("k2"), data("1"), data("name"), data("b")]),
+            Value::Bulk(vec![data("k3"), data("2")]),
+        ]);
+        let hits: Vec<SearchHit> = redis::from_redis_value(&reply).unwrap();
+        assert_eq!(hits.len(), 3);
+        assert_eq!(hits[0].key, "k1");
+        assert_eq!(hits[0].attributes.len(), 2);
+        assert_eq!(hits[0].attributes.get("price").unwrap(), "10");
+        assert_eq!(hits[1].distance, 1.0);
+        assert_eq!(hits[1].attributes.get("name").unwrap(), "b");
+        assert!(hits[2].attributes.is_empty());
+    }
+
+    #[test]
+    fn decode_multi() {
+        let reply = Value::Bulk(vec![
+            Value::Bulk(vec![data("k1"), data("0"), data("k2"), data("1")]),
+            Value::Bulk(vec![]),
+        ]);
+        let results: Vec<KnnResult> = redis::from_redis_value(&reply).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].len(), 2);
+        assert!(results[1].is_empty());
+    }
+
+    #[test]
+    fn helpers() {
+        let merged = KnnResult::merge(
+            vec![
+                KnnResult(vec![hit("a", 0.125), hit("b", 0.5)]),
+                KnnResult(vec![hit("c", 0.25), hit("d", 0.5), hit("e", 0.875)]),
+            ],
+            4,
+        );
+        let keys: Vec<&str> = merged.iter().map(|h| h.key.as_str()).collect();
+        assert_eq!(keys, ["a", "c", "b", "d"]);
+
+        assert_eq!(hit("a", 0.25).similarity(DistanceType::Cosine), Some(0.75));
+        assert_eq!(hit("a", 0.25).similarity(DistanceType::L2), None);
+        assert_eq!(
+            merged.similarities(DistanceType::Ip).unwrap()[0],
+            ("a".to_owned(), 0.875)
+        );
+        assert!(merged.similarities(DistanceType::L2).is_none());
+    }
+
+    #[test]
+    fn query_args() {
+        let args = |q: &KnnQuery| -> Vec<String> {
+            q.tail_args()
+                .to_redis_args()
+                .into_iter()
+                .map(|a| String::from_utf8(a).unwrap())
+                .collect()
+        };
+
+        let query = KnnQuery::new(10);
+        assert_eq!(query.command_name(), "TVS.KNNSEARCH");
+        assert!(args(&query).is_empty());
+
+        let query = KnnQuery::new(10)
+            .ef_search(200)
+            .filter(field("price").gt(10))
+            .max_dist(0.5)
+            .output_fields(&["name", "price"]);
+        assert_eq!(query.command_name(), "TVS.KNNSEARCHFIELD");
+        assert_eq!(
+            args(&query),
+            [
+                "2",
+                "name",
+                "price",
+                "price > 10",
+                "ef_search",
+                "200",
+                "MAX_DIST",
+                "0.5"
+            ]
+        );
+
+        let query = KnnQuery::new(3).filter_raw("a == 1").ef_search(20);
+        assert_eq!(args(&query), ["a == 1", "ef_search", "20"]);
+
+        let query = KnnQuery::new(3).filter(field("a").eq(f32::NAN));
+        assert!(query.check().is_err());
+        assert!(query.clone().filter_raw("a == 1").check().is_ok());
+    }
+
+    fn setup_index(conn: &mut redis::Connection, index_name: &str) {
+        // cleanup
+        conn.tvs_del_index::<_, usize>(index_name).unwrap();
+
+        let spec = IndexSpec::new(index_name, 2, IndexType::Hnsw, DistanceType::L2);
+        let created: bool = conn.tvs_create_index_spec(&spec).unwrap();
+        assert!(created);
+        for (i, price) in [5, 15, 25].into_iter().enumerate() {
+            let key = format!("k{}", i + 1);
+            let vector = crate::Vector::new(vec![i as f32, 0.0]).unwrap().to_string();
+            let price = price.to_string();
+            let name = format!("item {}", i + 1);
+            let _: usize = conn
+                .tvs_hset_multi(
+                    index_name,
+                    &key,
+                    &[
+                        ("VECTOR", vector.as_str()),
+                        ("price", &price),
+                        ("name", &name),
+                    ],
+                )
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn knn_query() {
+        let redis_url = crate::mock::test_url();
+
+        let index_name = "test-knn-query";
+        let client = redis::Client::open(redis_url).unwrap();
+        let mut conn = client.get_connection().unwrap();
+        setup_index(&mut conn, index_name);
+
+        let query = KnnQuery::new(2)
+            .ef_search(100)
+            .filter(field("price").gt(10))
+            .output_fields(&["name", "price"]);
+        let result: KnnResult = conn
+            .tvs_knnsearch_query(index_name, [0.0f32, 0.0], &query)
+            .unwrap();
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].key, "k2");
+        assert_eq!(result[0].attributes.get("name").unwrap(), "item 2");
+        assert_eq!(result[1].attributes.get("price").unwrap(), "25");
+
+        let query = KnnQuery::new(3).max_dist(1.5);
+        let result: KnnResult = conn
+            .tvs_knnsearch_query(index_name, [0.0f32, 0.0], &query)
+            .unwrap();
+        assert_eq!(result.len(), 2);
+        assert!(result[0].attributes.is_empty());
+
+        let query = KnnQuery::new(3).filter(field("price").gt(f64::INFINITY));
+        let err = conn
+            .tvs_knnsearch_query::<_, _, KnnResult>(index_name, [0.0f32, 0.0], &query)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ClientError);
+
+        conn.tvs_del_index::<_, usize>(index_name).unwrap();
+    }
+
+    #[cfg(feature = "aio")]
+    #[tokio::test]
+    async fn knn_query_async() {
+        use crate::TairVectorAsyncCommands;
+
+        let redis_url = crate::mock::test_url();
+
+        let index_name = "test-knn-query-async";
+        let client = redis::Client::open(redis_url).unwrap();
+        setup_index(&mut client.get_connection().unwrap(), index_name);
+
+        let mut conn = client.get_async_connection().await.unwrap();
+        let query = KnnQuery::new(1)
+            .filter(field("name").eq("item 3"))
+            .output_fields(&["price"]);
+        let result: KnnResult = conn
+            .tvs_knnsearch_query(index_name, vec![0.0f32, 0.0], &query)
+            .await
+            .unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].key, "k3");
+        assert_eq!(result[0].attributes.get("price").unwrap(), "25");
+
+        conn.tvs_del_index::<_, usize>(index_name).await.unwrap();
+    }
+}
@@ -0,0 +1,221 @@
+use crate::{check_elements, invalid_argument, parse_reply, vector_parse_error};
+use redis::{FromRedisValue, RedisError, RedisResult, RedisWrite, ToRedisArgs, Value};
+use std::fmt;
+use std::str;
+
+/// A sparse vector, e.g. BM25 term weights, for indices created with
+/// `LexicalAlgorithm::Vector`.
+///
+/// The server stores it in the `TEXT` field, encoded as
+/// `[[index1,value1],[index2,value2],...]`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SparseVector {
+    pub indices: Vec<u32>,
+    pub values: Vec<f32>,
+}
+
+impl SparseVector {
+    /// Builds a sparse vector from parallel `indices` and `values`, sorting
+    /// them by index. Duplicate indices and non-finite values are rejected.
+    pub fn new(indices: Vec<u32>, values: Vec<f32>) -> RedisResult<Self> {
+        if indices.len() != values.len() {
+            return Err(invalid_sparse(format!(
+                "{} indices but {} values",
+                indices.len(),
+                values.len()
+            )));
+        }
+        check_elements(values.iter())?;
+        let mut pairs: Vec<(u32, f32)> = indices.into_iter().zip(values).collect();
+        pairs.sort_by_key(|&(i, _)| i);
+        if let Some(w) = pairs.windows(2).find(|w| w[0].0 == w[1].0) {
+            return Err(invalid_sparse(format!("duplicate index {}", w[0].0)));
+        }
+        let (indices, values) = pairs.into_iter().unzip();
+        Ok(SparseVector { indices, values })
+    }
+
+    /// `new` from `(index, value)` pairs.
+    pub fn from_pairs<I: IntoIterator<Item = (u32, f32)>>(pairs: I) -> RedisResult<Self> {
+        let (indices, values) = pairs.into_iter().unzip();
+        SparseVector::new(indices, values)
+    }
+
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u32, f32)> + '_ {
+        self.indices
+            .iter()
+            .copied()
+            .zip(self.values.iter().copied())
+    }
+}
+
+fn invalid_sparse(detail: String) -> RedisError {
+    invalid_argument("Invalid sparse vector", detail)
+}
+
+impl fmt::Display for SparseVector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut buffer = ryu::Buffer::new();
+        f.write_str("[")?;
+        for (i, (index, value)) in self.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            let s = buffer.format(value);
+            write!(f, "[{},{}]", index, s.strip_suffix(".0").unwrap_or(s))?;
+        }
+        f.write_str("]")
+    }
+}
+
+impl ToRedisArgs for SparseVector {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        out.write_arg_fmt(self);
+    }
+}
+
+impl str::FromStr for SparseVector {
+    type Err = RedisError;
+
+    /// Parses the `[[index1,value1],...]` format used by TairVector.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let malformed = || vector_parse_error(format!("expected [[i,x],...], got {:?}", s));
+        let mut rest = s
+            .trim()
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+            .ok_or_else(malformed)?
+            .trim();
+
+        let mut vector = SparseVector::default();
+        while !rest.is_empty() {
+            let (pair, tail) = rest
+                .strip_prefix('[')
+                .and_then(|rest| rest.split_once(']'))
+                .ok_or_else(malformed)?;
+            let (index, value) = pair
+                .split_once(',')
+                .and_then(|(i, x)| Some((i.trim().parse().ok()?, x.trim().parse().ok()?)))
+                .ok_or_else(|| {
+                    vector_parse_error(format!("element {}: invalid pair {:?}", vector.len(), pair))
+                })?;
+            vector.indices.push(index);
+            vector.values.push(value);
+
+            rest = tail.trim_start();
+            if let Some(tail) = rest.strip_prefix(',') {
+                rest = tail.trim_start();
+                if rest.is_empty() {
+                    return Err(malformed());
+                }
+            } else if !rest.is_empty() {
+                return Err(malformed());
+            }
+        }
+        Ok(vector)
+    }
+}
+
+impl FromRedisValue for SparseVector {
+    fn from_redis_value(value: &Value) -> RedisResult<Self> {
+        parse_reply(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        DistanceType, IndexSpec, IndexType, KnnResult, LexicalAlgorithm, TairVectorCommands, Vector,
+    };
+
+    #[test]
+    fn encode_sparse() {
+        let v = SparseVector::new(vec![7, 2, 100], vec![0.25, 1.0, -3.5]).unwrap();
+        assert_eq!(v.indices, [2, 7, 100]);
+        assert_eq!(v.values, [1.0, 0.25, -3.5]);
+        assert_eq!(v.to_string(), "[[2,1],[7,0.25],[100,-3.5]]");
+        assert_eq!(
+            v.to_redis_args(),
+            vec![b"[[2,1],[7,0.25],[100,-3.5]]".to_vec()]
+        );
+
+        assert!(SparseVector::new(vec![1, 2], vec![1.0]).is_err());
+        assert!(SparseVector::new(vec![1, 1], vec![1.0, 2.0]).is_err());
+        assert!(SparseVector::new(vec![1], vec![f32::NAN]).is_err());
+        assert_eq!(SparseVector::new(vec![], vec![]).unwrap().to_string(), "[]");
+        assert_eq!(
+            SparseVector::from_pairs([(100, -3.5), (2, 1.0), (7, 0.25)]).unwrap(),
+            v
+        );
+        assert!(SparseVector::from_pairs([(1, 1.0), (1, 2.0)]).is_err());
+        assert!(SparseVector::from_pairs([(1, f32::INFINITY)]).is_err());
+
+        let decoded: SparseVector =
+            redis::from_redis_value(&Value::Data(v.to_string().into_bytes())).unwrap();
+        assert_eq!(decoded, v);
+        let decoded: SparseVector = " [ [2, 1.0] , [7,0.25] ] ".parse().unwrap();
+        assert_eq!(decoded.iter().collect::<Vec<_>>(), [(2, 1.0), (7, 0.25)]);
+        assert!("[]".parse::<SparseVector>().unwrap().is_empty());
+        assert!("[[1,x]]".parse::<SparseVector>().is_err());
+        assert!("[[-1,0.5]]".parse::<SparseVector>().is_err());
+        assert!("[1,2]".parse::<SparseVector>().is_err());
+        assert!("[[1,2],]".parse::<SparseVector>().is_err());
+        assert!("[[1,2][3,4]]".parse::<SparseVector>().is_err());
+    }
+
+    #[test]
+    fn hybrid_search() {
+        let redis_url = crate::mock::test_url();
+
+        let index_name = "test-hybrid-search";
+        let client = redis::Client::open(redis_url).unwrap();
+        let mut conn = client.get_connection().unwrap();
+
+        // cleanup
+        conn.tvs_del_index::<_, usize>(index_name).unwrap();
+
+        let spec = IndexSpec::new(index_name, 2, IndexType::Hnsw, DistanceType::Ip)
+            .lexical_algorithm(LexicalAlgorithm::Vector);
+        let created: bool = conn.tvs_create_index_spec(&spec).unwrap();
+        assert!(created);
+
+        let dense = Vector::new(vec![1.0, 0.0]).unwrap();
+        let sparse = SparseVector::new(vec![3, 10], vec![0.5, 0.5]).unwrap();
+        let _: usize = conn
+            .tvs_hset_hybrid(index_name, "k1", &dense, &sparse, &[("name", "a")])
+            .unwrap();
+        let _: usize = conn
+            .tvs_hset_hybrid(
+                index_name,
+                "k2",
+                Vector::new(vec![0.0, 1.0]).unwrap(),
+                SparseVector::new(vec![4], vec![1.0]).unwrap(),
+                &[] as &[(&str, &str)],
+            )
+            .unwrap();
+
+        let got: Vec<SparseVector> = conn.tvs_hmget(index_name, "k1", &["TEXT"]).unwrap();
+        assert_eq!(got[0], sparse);
+
+        let query = SparseVector::new(vec![3], vec![1.0]).unwrap();
+        let results: KnnResult = conn
+            .tvs_hybrid_knnsearch(index_name, 2, &dense, &query, 0.5, &[] as &[(&str, &str)])
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].key, "k1");
+
+        conn.tvs_del_index::<_, usize>(index_name).unwrap();
+    }
+}
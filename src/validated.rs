@@ -0,0 +1,294 @@
+#[cfg(feature = "aio")]
+use crate::TairVectorAsyncCommands;
+use crate::{AsVector, DataType, IndexInfo, TairVectorCommands, TairVectorError};
+use redis::{ConnectionLike, FromRedisValue, RedisResult, ToRedisArgs};
+
+/// An index handle that checks vectors against the dimension and data type
+/// of the index before sending them.
+///
+/// The metadata is read once with TVS.GETINDEX. When the server reports that
+/// the index is missing or does not take the vector, e.g. because it was
+/// dropped or recreated in the meantime, it is read again before the error is
+/// returned.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidatedIndex {
+    name: String,
+    dimension: usize,
+    data_type: DataType,
+}
+
+impl ValidatedIndex {
+    pub fn open<C: ConnectionLike>(conn: &mut C, name: &str) -> Result<Self, TairVectorError> {
+        let info = conn.tvs_get_index_info(name)?;
+        ValidatedIndex::from_info(name, info)
+    }
+
+    #[cfg(feature = "aio")]
+    pub async fn open_async<C: redis::aio::ConnectionLike + Send>(
+        conn: &mut C,
+        name: &str,
+    ) -> Result<Self, TairVectorError> {
+        let info = conn.tvs_get_index_info(name).await?;
+        ValidatedIndex::from_info(name, info)
+    }
+
+    fn from_info(name: &str, info: Option<IndexInfo>) -> Result<Self, TairVectorError> {
+        let info = info.ok_or_else(|| TairVectorError::IndexNotFound(name.to_owned()))?;
+        Ok(ValidatedIndex {
+            name: name.to_owned(),
+            dimension: info.dimension,
+            data_type: info.data_type,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    pub fn data_type(&self) -> DataType {
+        self.data_type
+    }
+
+    /// Reads the index metadata again.
+    pub fn refresh<C: ConnectionLike>(&mut self, conn: &mut C) -> Result<(), TairVectorError> {
+        *self = ValidatedIndex::open(conn, &self.name)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "aio")]
+    pub async fn refresh_async<C: redis::aio::ConnectionLike + Send>(
+        &mut self,
+        conn: &mut C,
+    ) -> Result<(), TairVectorError> {
+        *self = ValidatedIndex::open_async(conn, &self.name).await?;
+        Ok(())
+    }
+
+    /// Checks `vector` against the cached metadata, without a round trip.
+    ///
+    /// Float vectors of any precision are accepted by float indices, while
+    /// BINARY indices only take `BinaryVector`s.
+    pub fn check<V: AsVector + ?Sized>(&self, vector: &V) -> Result<(), TairVectorError> {
+        if vector.dim() != self.dimension {
+            return Err(TairVectorError::DimensionMismatch {
+                expected: Some(self.dimension),
+                actual: Some(vector.dim()),
+            });
+        }
+        if (vector.data_type() == DataType::Binary) != (self.data_type == DataType::Binary) {
+            return Err(TairVectorError::UnsupportedDataType(format!(
+                "index {} stores {} vectors, got {}",
+                self.name,
+                self.data_type,
+                vector.data_type()
+            )));
+        }
+        Ok(())
+    }
+
+    /// TVS.HSET index_name key VECTOR vector, after checking `vector`
+    pub fn hset_vector<C: ConnectionLike, K: ToRedisArgs, V: AsVector, RV: FromRedisValue>(
+        &mut self,
+        conn: &mut C,
+        key: K,
+        vector: V,
+    ) -> Result<RV, TairVectorError> {
+        self.check(&vector)?;
+        let result = conn.tvs_hset_vector(&self.name, key, vector);
+        self.refresh_on_error(conn, result)
+    }
+
+    /// TVS.KNNSEARCH index_name topk vector, after checking `vector`
+    pub fn knnsearch<C: ConnectionLike, V: AsVector, RV: FromRedisValue>(
+        &mut self,
+        conn: &mut C,
+        topk: usize,
+        vector: V,
+    ) -> Result<RV, TairVectorError> {
+        self.check(&vector)?;
+        let result = conn.tvs_knnsearch(&self.name, topk, vector);
+        self.refresh_on_error(conn, result)
+    }
+
+    fn refresh_on_error<C: ConnectionLike, RV>(
+        &mut self,
+        conn: &mut C,
+        result: RedisResult<RV>,
+    ) -> Result<RV, TairVectorError> {
+        match result.map_err(TairVectorError::from) {
+            Err(e) if is_stale(&e) => {
+                // report the error of the command, a failed refresh keeps
+                // the cached metadata until the next stale error
+                let _ = self.refresh(conn);
+                Err(e)
+            }
+            result => result,
+        }
+    }
+
+    #[cfg(feature = "aio")]
+    pub async fn hset_vector_async<C, K, V, RV>(
+        &mut self,
+        conn: &mut C,
+        key: K,
+        vector: V,
+    ) -> Result<RV, TairVectorError>
+    where
+        C: redis::aio::ConnectionLike + Send,
+        K: ToRedisArgs + Send + Sync,
+        V: AsVector + Send + Sync,
+        RV: FromRedisValue,
+    {
+        self.check(&vector)?;
+        let result = conn.tvs_hset_vector(&self.name, key, vector).await;
+        self.refresh_on_error_async(conn, result).await
+    }
+
+    #[cfg(feature = "aio")]
+    pub async fn knnsearch_async<C, V, RV>(
+        &mut self,
+        conn: &mut C,
+        topk: usize,
+        vector: V,
+    ) -> Result<RV, TairVectorError>
+    where
+        C: redis::aio::ConnectionLike + Send,
+        V: AsVector + Send + Sync,
+        RV: FromRedisValue,
+    {
+        self.check(&vector)?;
+        let result = conn.tvs_knnsearch(&self.name, topk, vector).await;
+        self.refresh_on_error_async(conn, result).await
+    }
+
+    #[cfg(feature = "aio")]
+    async fn refresh_on_error_async<C: redis::aio::ConnectionLike + Send, RV>(
+        &mut self,
+        conn: &mut C,
+        result: RedisResult<RV>,
+    ) -> Result<RV, TairVectorError> {
+        match result.map_err(TairVectorError::from) {
+            Err(e) if is_stale(&e) => {
+                // report the error of the command, a failed refresh keeps
+                // the cached metadata until the next stale error
+                let _ = self.refresh_async(conn).await;
+                Err(e)
+            }
+            result => result,
+        }
+    }
+}
+
+/// Server errors that may mean the index changed since it was opened.
+fn is_stale(e: &TairVectorError) -> bool {
+    matches!(
+        e,
+        TairVectorError::IndexNotFound(_)
+            | TairVectorError::DimensionMismatch { .. }
+            | TairVectorError::UnsupportedDataType(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BinaryVector, DistanceType, IndexSpec, IndexType, Vector};
+
+    #[test]
+    fn check_vectors() {
+        let index = ValidatedIndex {
+            name: "idx".to_owned(),
+            dimension: 3,
+            data_type: DataType::Float32,
+        };
+        assert!(index.check(&[1.0f32, 2.0, 3.0]).is_ok());
+        assert!(index
+            .check(&Vector::new(vec![1.0, 2.0, 3.0]).unwrap())
+            .is_ok());
+        assert!(matches!(
+            index.check(&[1.0f32, 2.0]),
+            Err(TairVectorError::DimensionMismatch {
+                expected: Some(3),
+                actual: Some(2)
+            })
+        ));
+        let bits = BinaryVector::from_bools(&[true, false, true]);
+        assert!(matches!(
+            index.check(&bits),
+            Err(TairVectorError::UnsupportedDataType(_))
+        ));
+
+        let index = ValidatedIndex {
+            data_type: DataType::Binary,
+            ..index
+        };
+        assert!(index.check(&bits).is_ok());
+        assert!(index.check(&[1.0f32, 0.0, 1.0]).is_err());
+    }
+
+    #[test]
+    fn validated_index() {
+        let redis_url = crate::mock::test_url();
+
+        let index_name = "test-validated-index";
+        let client = redis::Client::open(redis_url).unwrap();
+        let mut conn = client.get_connection().unwrap();
+
+        // cleanup
+        conn.tvs_del_index::<_, usize>(index_name).unwrap();
+
+        assert!(matches!(
+            ValidatedIndex::open(&mut conn, index_name),
+            Err(TairVectorError::IndexNotFound(_))
+        ));
+
+        let spec = IndexSpec::new(index_name, 2, IndexType::Flat, DistanceType::L2);
+        let created: bool = conn.tvs_create_index_spec(&spec).unwrap();
+        assert!(created);
+
+        let mut index = ValidatedIndex::open(&mut conn, index_name).unwrap();
+        assert_eq!(index.dimension(), 2);
+        assert_eq!(index.data_type(), DataType::Float32);
+
+        let _: usize = index.hset_vector(&mut conn, "k1", [1.0f32, 2.0]).unwrap();
+        let err = index
+            .hset_vector::<_, _, _, usize>(&mut conn, "k2", [1.0f32, 2.0, 3.0])
+            .unwrap_err();
+        assert!(matches!(err, TairVectorError::DimensionMismatch { .. }));
+        let index_info: IndexInfo = conn.tvs_get_index(index_name).unwrap();
+        assert_eq!(index_info.data_count, 1);
+
+        let results: Vec<(String, f32)> = index.knnsearch(&mut conn, 1, [1.0f32, 2.0]).unwrap();
+        assert_eq!(results[0].0, "k1");
+
+        // the index is recreated with another dimension behind our back
+        conn.tvs_del_index::<_, usize>(index_name).unwrap();
+        let spec = IndexSpec::new(index_name, 3, IndexType::Flat, DistanceType::L2);
+        let _: bool = conn.tvs_create_index_spec(&spec).unwrap();
+        // the stale dimension passes the local check but fails on the server,
+        // which refreshes the cache
+        assert_eq!(index.dimension(), 2);
+        assert!(index
+            .hset_vector::<_, _, _, usize>(&mut conn, "k1", [1.0f32, 2.0])
+            .is_err());
+        assert_eq!(index.dimension(), 3);
+        let _: usize = index
+            .hset_vector(&mut conn, "k1", [1.0f32, 2.0, 3.0])
+            .unwrap();
+
+        // the refresh fails too, the error of the command is returned
+        conn.tvs_del_index::<_, usize>(index_name).unwrap();
+        let err = index
+            .knnsearch::<_, _, Vec<(String, f32)>>(&mut conn, 1, [1.0f32, 2.0, 3.0])
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            TairVectorError::IndexNotFound(m) if m != index_name
+        ));
+        assert_eq!(index.dimension(), 3);
+    }
+}